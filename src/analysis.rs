@@ -0,0 +1,164 @@
+use crate::Instruction;
+use decasm::{Byte, Register};
+use std::ops::RangeInclusive;
+use ux::u4;
+
+/// The registers an instruction reads and writes, for static analysis.
+///
+/// Both arrays are indexed by register number, with V0 through VF at indices 0 through 15 and
+/// the index register I at index [`INDEX`](InstructionRegisters::INDEX). Where an instruction's
+/// behavior depends on quirks, the registers it might access under any quirk setting are included.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InstructionRegisters {
+    /// The registers the instruction reads.
+    pub reads: [bool; 17],
+    /// The registers the instruction writes.
+    pub writes: [bool; 17],
+}
+
+impl InstructionRegisters {
+    /// The position of the index register I in [`reads`](InstructionRegisters::reads) and
+    /// [`writes`](InstructionRegisters::writes).
+    pub const INDEX: usize = 16;
+}
+
+/// An extension trait for analyzing which registers an [`Instruction`] accesses.
+pub trait AffectedRegisters {
+    /// The registers this instruction reads and writes when executed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::AffectedRegisters;
+    /// // v1 += v2
+    /// let registers = deca::Instruction::try_from(0x8124).unwrap().affected_registers();
+    /// assert!(registers.reads[1] && registers.reads[2]);
+    /// assert!(registers.writes[1] && registers.writes[0xF]);
+    /// ```
+    fn affected_registers(&self) -> InstructionRegisters;
+}
+
+fn register(x: u4) -> usize {
+    usize::from(u8::from(x))
+}
+
+/// The registers from X to Y, in either order.
+fn range(x: u4, y: u4) -> RangeInclusive<usize> {
+    let (x, y) = (register(x), register(y));
+    x.min(y)..=x.max(y)
+}
+
+impl AffectedRegisters for Instruction {
+    #[allow(clippy::too_many_lines)]
+    fn affected_registers(&self) -> InstructionRegisters {
+        const I: usize = InstructionRegisters::INDEX;
+        const VF: usize = 0xF;
+        let mut registers = InstructionRegisters::default();
+        let InstructionRegisters { reads, writes } = &mut registers;
+        match *self {
+            Instruction::Exit(_)
+            | Instruction::ScrollUp(_)
+            | Instruction::ScrollDown(_)
+            | Instruction::Clear
+            | Instruction::Return
+            | Instruction::ToggleLoadStoreQuirk
+            | Instruction::ScrollRight
+            | Instruction::ScrollLeft
+            | Instruction::LoRes
+            | Instruction::HiRes
+            | Instruction::CallMachineCode(_)
+            | Instruction::Jump(_)
+            | Instruction::Call(_)
+            | Instruction::SelectPlane(_)
+            | Instruction::SoundStuffTwo => {}
+            Instruction::SkipIfEqual(Register(x), Byte::Immediate(_))
+            | Instruction::SkipIfNotEqual(Register(x), Byte::Immediate(_))
+            | Instruction::SkipKey(Register(x))
+            | Instruction::SkipNotKey(Register(x))
+            | Instruction::SetDelay(Register(x))
+            | Instruction::SetSound(Register(x)) => reads[register(x)] = true,
+            Instruction::SkipIfEqual(Register(x), Byte::Register(Register(y)))
+            | Instruction::SkipIfNotEqual(Register(x), Byte::Register(Register(y))) => {
+                reads[register(x)] = true;
+                reads[register(y)] = true;
+            }
+            Instruction::Set(Register(x), Byte::Immediate(_))
+            | Instruction::Random(Register(x), _)
+            | Instruction::LoadDelay(Register(x))
+            | Instruction::BlockKey(Register(x)) => writes[register(x)] = true,
+            Instruction::Set(Register(x), Byte::Register(Register(y))) => {
+                reads[register(y)] = true;
+                writes[register(x)] = true;
+            }
+            Instruction::Add(Register(x), Byte::Immediate(_)) => {
+                reads[register(x)] = true;
+                writes[register(x)] = true;
+            }
+            Instruction::Or(Register(x), Register(y))
+            | Instruction::And(Register(x), Register(y))
+            | Instruction::Xor(Register(x), Register(y)) => {
+                reads[register(x)] = true;
+                reads[register(y)] = true;
+                writes[register(x)] = true;
+            }
+            Instruction::Add(Register(x), Byte::Register(Register(y)))
+            | Instruction::Sub(Register(x), Register(y))
+            | Instruction::SubReverse(Register(x), Register(y))
+            | Instruction::ShiftLeft(Register(x), Register(y))
+            | Instruction::ShiftRight(Register(x), Register(y)) => {
+                reads[register(x)] = true;
+                reads[register(y)] = true;
+                writes[register(x)] = true;
+                writes[VF] = true;
+            }
+            Instruction::SetIndex(_) | Instruction::SetIndexLong => writes[I] = true,
+            // With the jump0 quirk, the register is given by the highest nibble of the address
+            Instruction::JumpRelative(nnn) => {
+                reads[0] = true;
+                reads[usize::from((nnn & 0x0F00) >> 8)] = true;
+            }
+            Instruction::Draw(Register(x), Register(y), _) => {
+                reads[register(x)] = true;
+                reads[register(y)] = true;
+                reads[I] = true;
+                writes[VF] = true;
+            }
+            Instruction::SoundStuff => reads[I] = true,
+            Instruction::AddRegisterToIndex(Register(x)) => {
+                reads[register(x)] = true;
+                reads[I] = true;
+                writes[I] = true;
+            }
+            Instruction::FontCharacter(Register(x))
+            | Instruction::BigFontCharacter(Register(x)) => {
+                reads[register(x)] = true;
+                writes[I] = true;
+            }
+            Instruction::Bcd(Register(x)) => {
+                reads[register(x)] = true;
+                reads[I] = true;
+            }
+            Instruction::Store(Register(x)) => {
+                reads[..=register(x)].fill(true);
+                reads[I] = true;
+                writes[I] = true;
+            }
+            Instruction::Load(Register(x)) => {
+                reads[I] = true;
+                writes[..=register(x)].fill(true);
+                writes[I] = true;
+            }
+            Instruction::StoreRange(Register(x), Register(y)) => {
+                reads[range(x, y)].fill(true);
+                reads[I] = true;
+            }
+            Instruction::LoadRange(Register(x), Register(y)) => {
+                reads[I] = true;
+                writes[range(x, y)].fill(true);
+            }
+            Instruction::StoreFlags(Register(x)) => reads[..=register(x)].fill(true),
+            Instruction::LoadFlags(Register(x)) => writes[..=register(x)].fill(true),
+        }
+        registers
+    }
+}
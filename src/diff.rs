@@ -0,0 +1,57 @@
+use std::fmt;
+
+/// The differences between the states of two CHIP-8 interpreters, returned by
+/// [`Chip8::state_diff`](crate::Chip8::state_diff).
+///
+/// Each list holds the value in the first interpreter followed by the value in the second.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Chip8Diff {
+    /// Whether the Program Counters differ.
+    pub pc_differs: bool,
+    /// Whether the index registers differ.
+    pub i_differs: bool,
+    /// Whether the call stacks differ, in their depth or in any return address on them.
+    pub stack_differs: bool,
+    /// Whether the delay or sound timers differ.
+    pub timers_differ: bool,
+    /// The variable registers that differ, as `(register, self_value, other_value)`.
+    pub registers: Vec<(usize, u8, u8)>,
+    /// The bytes of program memory that differ, as `(address, self_value, other_value)`.
+    pub memory_diffs: Vec<(u16, u8, u8)>,
+    /// The pixels that differ, as `(x, y, self_value, other_value)`, like
+    /// [`Display::compare`](crate::Display::compare).
+    pub display_diffs: Vec<(u8, u8, u8, u8)>,
+}
+
+impl Chip8Diff {
+    /// Whether the interpreters' states are the same.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Chip8Diff::default()
+    }
+}
+
+/// A list of the differences, one per line.
+impl fmt::Display for Chip8Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flags = [
+            (self.pc_differs, "PC"),
+            (self.i_differs, "I"),
+            (self.stack_differs, "stack"),
+            (self.timers_differ, "timers"),
+        ];
+        for (_, name) in flags.iter().filter(|(differs, _)| *differs) {
+            writeln!(f, "{name} differs")?;
+        }
+        for (n, a, b) in &self.registers {
+            writeln!(f, "V{n:X}: {a:#04X} != {b:#04X}")?;
+        }
+        for (address, a, b) in &self.memory_diffs {
+            writeln!(f, "{address:#06X}: {a:#04X} != {b:#04X}")?;
+        }
+        for (x, y, a, b) in &self.display_diffs {
+            writeln!(f, "({x}, {y}): {a} != {b}")?;
+        }
+        Ok(())
+    }
+}
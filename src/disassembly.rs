@@ -0,0 +1,56 @@
+use crate::Instruction;
+
+/// The assembly mnemonic of an instruction, in the style of Cowgod's CHIP-8 technical reference
+/// and its SUPER-CHIP and XO-CHIP extensions.
+#[allow(clippy::match_same_arms)]
+pub(crate) fn mnemonic(instruction: &Instruction) -> &'static str {
+    match instruction {
+        Instruction::Exit(_) => "EXIT",
+        Instruction::ScrollUp(_) => "SCU",
+        Instruction::ScrollDown(_) => "SCD",
+        Instruction::Clear => "CLS",
+        Instruction::Return => "RET",
+        Instruction::ToggleLoadStoreQuirk => "QUIRK",
+        Instruction::ScrollRight => "SCR",
+        Instruction::ScrollLeft => "SCL",
+        Instruction::LoRes => "LOW",
+        Instruction::HiRes => "HIGH",
+        Instruction::CallMachineCode(_) => "SYS",
+        Instruction::Jump(_) | Instruction::JumpRelative(_) => "JP",
+        Instruction::Call(_) => "CALL",
+        Instruction::SkipIfEqual(..) => "SE",
+        Instruction::SkipIfNotEqual(..) => "SNE",
+        Instruction::Add(..) | Instruction::AddRegisterToIndex(_) => "ADD",
+        Instruction::Or(..) => "OR",
+        Instruction::And(..) => "AND",
+        Instruction::Xor(..) => "XOR",
+        Instruction::Sub(..) => "SUB",
+        // Despite its name, decasm's ShiftLeft is 8XY6, which shifts right
+        Instruction::ShiftLeft(..) => "SHR",
+        Instruction::SubReverse(..) => "SUBN",
+        Instruction::ShiftRight(..) => "SHL",
+        Instruction::Random(..) => "RND",
+        Instruction::Draw(..) => "DRW",
+        Instruction::SkipKey(_) => "SKP",
+        Instruction::SkipNotKey(_) => "SKNP",
+        Instruction::SoundStuff => "AUDIO",
+        Instruction::SelectPlane(_) => "PLANE",
+        Instruction::SoundStuffTwo => "PITCH",
+        Instruction::StoreRange(..) => "SAVE",
+        Instruction::LoadRange(..) => "LOAD",
+        Instruction::Set(..)
+        | Instruction::SetIndex(_)
+        | Instruction::SetIndexLong
+        | Instruction::LoadDelay(_)
+        | Instruction::BlockKey(_)
+        | Instruction::SetDelay(_)
+        | Instruction::SetSound(_)
+        | Instruction::FontCharacter(_)
+        | Instruction::BigFontCharacter(_)
+        | Instruction::Bcd(_)
+        | Instruction::Store(_)
+        | Instruction::Load(_)
+        | Instruction::StoreFlags(_)
+        | Instruction::LoadFlags(_) => "LD",
+    }
+}
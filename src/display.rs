@@ -1,7 +1,27 @@
+use crate::Chip8Error;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+/// The minimum width of the display buffer, enough for SUPER-CHIP's high-resolution mode.
+const MIN_WIDTH: usize = 128;
+/// The minimum height of the display buffer, enough for SUPER-CHIP's high-resolution mode.
+const MIN_HEIGHT: usize = 64;
+
+/// Flags that control how [`Display::draw_raw_sprite`] draws a sprite.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DrawFlags {
+    /// Whether sprites that cross the edge of the display should wrap around to the other side,
+    /// instead of being clipped.
+    pub wrap: bool,
+}
+
 /// A struct representing a CHIP-8 display.
 pub struct Display {
-    /// The display buffer.
-    pub display: [[u8; 128]; 64],
+    /// The display buffer, stored row by row with [`stride`](Display::stride) pixels per row.
+    pub display: Vec<u8>,
+    /// The number of pixels in each row of the display buffer. This is at least the width of the
+    /// current display.
+    pub stride: usize,
     /// A dirty flag denoting whether the display buffer has changed or not. This can be used by a frontend
     /// to minimize drawing calls when the display is unchanged. When reading the display buffer, the
     /// frontend should unset this flag.
@@ -12,11 +32,54 @@ pub struct Display {
     /// A flag denoting whether the display is currently in high-resolution mode or not.
     pub hires: bool,
     /// The width of the current display in pixels.
-    pub width: u8,
+    pub width: u16,
     /// The height of the current display in pixels.
-    pub height: u8,
+    pub height: u16,
     /// The currently active bitplane, for XO-CHIP compatibility.
     pub active_plane: u8,
+    /// The number of frames the interpreter has run. This is incremented by [`Chip8::run`](crate::Chip8::run).
+    pub frame_number: u64,
+    dirty_frame: u64,
+    /// The X coordinate of the most recently drawn sprite, wrapped to the display.
+    pub last_draw_x: u8,
+    /// The Y coordinate of the most recently drawn sprite, wrapped to the display.
+    pub last_draw_y: u8,
+    /// The width in pixels of the most recently drawn sprite.
+    pub last_draw_width: u8,
+    /// The height in pixels of the most recently drawn sprite.
+    pub last_draw_height: u8,
+    /// Whether the most recently drawn sprite collided with any lit pixels.
+    pub last_draw_collision: bool,
+    /// The index in the four-color XO-CHIP palette of the color of pixels that aren't set in any
+    /// plane, from 0 to 3.
+    pub background_color: u8,
+    /// The number of sprites drawn since the display was created, or since
+    /// [`reset_counters`](Display::reset_counters) was called.
+    ///
+    /// Each plane a sprite is drawn to counts separately, so an XO-CHIP sprite drawn to both
+    /// planes counts twice. [`Chip8::draw_calls`](crate::Chip8::draw_calls) counts the
+    /// [`Draw`](crate::Instruction::Draw) instructions instead.
+    pub total_draw_calls: u64,
+    /// The number of drawn sprites that collided with lit pixels.
+    pub total_collision_count: u64,
+    /// The number of times any display plane was cleared.
+    pub total_clear_count: u64,
+}
+
+impl Clone for Display {
+    fn clone(&self) -> Self {
+        Display {
+            display: self.display.clone(),
+            ..*self
+        }
+    }
+
+    // Reuse the existing display buffer instead of allocating a new one
+    fn clone_from(&mut self, source: &Self) {
+        let mut display = std::mem::take(&mut self.display);
+        display.clone_from(&source.display);
+        *self = Display { display, ..*source };
+    }
 }
 
 impl Display {
@@ -24,167 +87,683 @@ impl Display {
     #[must_use]
     pub fn new() -> Display {
         Display {
-            display: [[0; 128]; 64],
+            display: vec![0; MIN_WIDTH * MIN_HEIGHT],
+            stride: MIN_WIDTH,
             dirty: false,
             clear: true,
             hires: false,
             width: 64,
             height: 32,
             active_plane: 1,
+            frame_number: 0,
+            dirty_frame: 0,
+            last_draw_x: 0,
+            last_draw_y: 0,
+            last_draw_width: 0,
+            last_draw_height: 0,
+            last_draw_collision: false,
+            background_color: 0,
+            total_draw_calls: 0,
+            total_collision_count: 0,
+            total_clear_count: 0,
         }
     }
 
-    /// Clear the currently active display plane.
+    /// Reset the [`total_draw_calls`](Display::total_draw_calls),
+    /// [`total_collision_count`](Display::total_collision_count) and
+    /// [`total_clear_count`](Display::total_clear_count) counters to zero.
+    pub fn reset_counters(&mut self) {
+        self.total_draw_calls = 0;
+        self.total_collision_count = 0;
+        self.total_clear_count = 0;
+    }
+
+    /// The [`frame_number`](Display::frame_number) when the display buffer last changed.
+    ///
+    /// A frontend can compare this to the frame number of the last display buffer it drew, and
+    /// skip drawing if they match.
+    #[must_use]
+    pub fn dirty_since_frame(&self) -> u64 {
+        self.dirty_frame
+    }
+
+    /// Set the dirty flag and record the current frame number.
+    fn mark_dirty(&mut self) {
+        self.dirty = true;
+        self.dirty_frame = self.frame_number;
+    }
+
+    /// Clear the currently active display plane, or all planes if `all_planes` is `true`.
+    #[deprecated(note = "use `clear_active_plane` or `clear_all_planes` instead")]
     pub fn clear(&mut self, all_planes: bool) {
-        for y in self.display.iter_mut() {
-            for pixel in y.iter_mut() {
-                if all_planes {
-                    *pixel = 0;
-                } else {
-                    *pixel &= !self.active_plane;
-                }
-            }
+        if all_planes {
+            self.clear_all_planes();
+        } else {
+            self.clear_active_plane();
         }
+    }
 
-        self.dirty = true;
-        self.clear = true;
+    /// Clear the currently active display plane.
+    pub fn clear_active_plane(&mut self) {
+        let active_plane = self.active_plane;
+        self.clear_planes(active_plane);
+    }
+
+    /// Clear all display planes.
+    pub fn clear_all_planes(&mut self) {
+        self.clear_planes(u8::MAX);
+    }
+
+    fn clear_planes(&mut self, planes: u8) {
+        let mut lit = false;
+        for pixel in &mut self.display {
+            *pixel &= !planes;
+            lit |= *pixel != 0;
+        }
+
+        self.mark_dirty();
+        // Other planes might still have pixels lit
+        self.clear = !lit;
+        self.total_clear_count += 1;
     }
 
     /// Draw a sprite at the given coordinates in the currently active display plane.
+    ///
+    /// The display is only marked as [`dirty`](Display::dirty) if a pixel was flipped, and as no
+    /// longer [`clear`](Display::clear) if a pixel was turned on.
+    ///
+    /// Only the active plane's bit of each pixel is toggled and checked for collisions, so an
+    /// XO-CHIP sprite can be drawn in several planes by selecting and drawing each plane in turn,
+    /// with the same result as drawing them all at once.
     // TODO: Observe clip and collision quirks.
     pub fn draw(&mut self, sprite: Vec<Vec<u8>>, x: u8, y: u8) -> u8 {
-        let x = x % self.width as u8;
-        let y = y % self.height as u8;
-        let mut collision = 0;
+        self.draw_with_row_info(sprite, x, y, None)
+    }
+
+    /// Draw a sprite like [`draw`](Display::draw), and also report which rows of the sprite
+    /// collided with existing pixels.
+    ///
+    /// If `row_collisions` is given, bit `n` of it is set if row `n` of the sprite had a
+    /// collision, for the first 16 rows. The return value is still 1 if any pixels collided, 0
+    /// otherwise.
+    pub fn draw_with_row_info(
+        &mut self,
+        sprite: Vec<Vec<u8>>,
+        x: u8,
+        y: u8,
+        mut row_collisions: Option<&mut u16>,
+    ) -> u8 {
+        if let Some(row_collisions) = row_collisions.as_deref_mut() {
+            *row_collisions = 0;
+        }
+        let (width, height) = (usize::from(self.width), usize::from(self.height));
+        let x = usize::from(x) % width;
+        let y = usize::from(y) % height;
+        let sprite_width = sprite.iter().map(Vec::len).max().unwrap_or(0);
+        let sprite_height = sprite.len();
+        let (mut collision, mut flipped, mut lit) = (0, false, false);
         for (row, sprite_row) in sprite.into_iter().enumerate() {
-            if row + y as usize >= self.height as usize {
+            if row + y >= height {
                 break;
             }
             for (col, pixel) in sprite_row.iter().enumerate() {
-                if col + x as usize >= self.width as usize {
+                if col + x >= width {
                     break;
                 }
-                if *pixel == 1 {
-                    let display_pixel = &mut self.display[y as usize + row][x as usize + col];
-                    if *display_pixel & self.active_plane == 0 {
-                        *display_pixel |= self.active_plane;
+                if *pixel == 1 && self.active_plane != 0 {
+                    flipped = true;
+                    if self.toggle_pixel(x + col, y + row) {
+                        collision = 1;
+                        if let Some(row_collisions) = row_collisions.as_deref_mut() {
+                            if row < 16 {
+                                *row_collisions |= 1 << row;
+                            }
+                        }
                     } else {
-                        *display_pixel &= !self.active_plane;
+                        lit = true;
+                    }
+                }
+            }
+        }
+        self.mark_drawn(flipped, lit);
+        self.record_draw(x, y, sprite_width, sprite_height, collision);
+        collision
+    }
+
+    /// Draw a sprite at the given coordinates in the currently active display plane, from the raw
+    /// sprite data as it's stored in CHIP-8 memory.
+    ///
+    /// The sprite is `width` pixels wide, which should be 8 or 16, so each row of the sprite is
+    /// one or two bytes of `data`. Returns 1 if any pixels collided, 0 otherwise.
+    pub fn draw_raw_sprite(
+        &mut self,
+        x: u8,
+        y: u8,
+        data: &[u8],
+        width: u8,
+        flags: DrawFlags,
+    ) -> u8 {
+        let (display_width, display_height) = (usize::from(self.width), usize::from(self.height));
+        let x = usize::from(x) % display_width;
+        let y = usize::from(y) % display_height;
+        let bytes_per_row = usize::from(width).div_ceil(8).max(1);
+        let (mut collision, mut flipped, mut lit) = (0, false, false);
+        for (row, sprite_row) in data.chunks(bytes_per_row).enumerate() {
+            let mut display_y = y + row;
+            if display_y >= display_height {
+                if !flags.wrap {
+                    break;
+                }
+                display_y %= display_height;
+            }
+            for col in 0..usize::from(width) {
+                let mut display_x = x + col;
+                if display_x >= display_width {
+                    if !flags.wrap {
+                        break;
+                    }
+                    display_x %= display_width;
+                }
+                let pixel = sprite_row
+                    .get(col / 8)
+                    .map_or(0, |byte| (byte << (col % 8)) >> 7);
+                if pixel == 1 && self.active_plane != 0 {
+                    flipped = true;
+                    if self.toggle_pixel(display_x, display_y) {
                         collision = 1;
-                    };
+                    } else {
+                        lit = true;
+                    }
                 }
             }
         }
-        self.clear = false;
-        self.dirty = true;
+        self.mark_drawn(flipped, lit);
+        let height = data.len().div_ceil(bytes_per_row);
+        self.record_draw(x, y, usize::from(width), height, collision);
         collision
     }
 
-    /// Scroll the currently active display plane up.
+    /// Update the dirty and clear flags after drawing a sprite, depending on whether any pixels
+    /// were flipped and whether any of them were turned on.
+    fn mark_drawn(&mut self, flipped: bool, lit: bool) {
+        if flipped {
+            self.mark_dirty();
+        }
+        if lit {
+            self.clear = false;
+        }
+    }
+
+    /// Remember the position, size and collision of the most recently drawn sprite.
+    #[allow(clippy::cast_possible_truncation)]
+    fn record_draw(&mut self, x: usize, y: usize, width: usize, height: usize, collision: u8) {
+        self.last_draw_x = x as u8;
+        self.last_draw_y = y as u8;
+        self.last_draw_width = width.min(usize::from(u8::MAX)) as u8;
+        self.last_draw_height = height.min(usize::from(u8::MAX)) as u8;
+        self.last_draw_collision = collision != 0;
+        self.total_draw_calls += 1;
+        if collision != 0 {
+            self.total_collision_count += 1;
+        }
+    }
+
+    /// Flip a pixel in the currently active plane, returning whether it was previously set.
+    fn toggle_pixel(&mut self, x: usize, y: usize) -> bool {
+        let index = self.index(x, y);
+        let display_pixel = &mut self.display[index];
+        if *display_pixel & self.active_plane == 0 {
+            *display_pixel |= self.active_plane;
+            false
+        } else {
+            *display_pixel &= !self.active_plane;
+            true
+        }
+    }
+
+    /// Whether scrolling up or down might change anything. Scrolling a
+    /// [`clear`](Display::clear) display is skipped, since there are no pixels to move.
+    ///
+    /// The `clear` flag only approximates an empty display: it's set by [`resize`](Display::resize)
+    /// and by clearing the last lit plane, but a sprite that erases the last lit pixels leaves it
+    /// unset, and it covers the whole buffer and every plane. So this can return `true` even if
+    /// scrolling doesn't change any visible pixels of the active plane.
+    #[must_use]
+    pub fn can_scroll_vertical(&self) -> bool {
+        !self.clear
+    }
+
+    /// Whether scrolling left or right might change anything; see
+    /// [`can_scroll_vertical`](Display::can_scroll_vertical).
+    #[must_use]
+    pub fn can_scroll_horizontal(&self) -> bool {
+        self.can_scroll_vertical()
+    }
+
+    /// Scroll the currently active display plane up by the given number of pixels.
+    ///
+    /// Scrolling by the height of the display or more clears the plane.
     pub fn scroll_up(&mut self, pixels: u8) {
         if !self.clear && pixels > 0 {
-            for y in pixels..self.height {
-                for x in 0..self.width {
-                    self.display[(y - pixels) as usize][x as usize] |=
-                        self.display[y as usize][x as usize] & self.active_plane;
-                    self.display[y as usize][x as usize] &= !self.active_plane;
+            let (width, height, pixels) = self.scroll_bounds(pixels);
+            if pixels >= height {
+                self.clear_active_plane();
+                return;
+            }
+            for y in pixels..height {
+                for x in 0..width {
+                    self.move_pixel((x, y), (x, y - pixels));
                 }
             }
-            for y in (self.height - pixels)..self.height {
-                for x in 0..=self.width {
-                    self.display[y as usize][x as usize] &= !self.active_plane;
+            for y in (height - pixels)..height {
+                for x in 0..width {
+                    self.clear_pixel(x, y);
                 }
             }
 
-            self.dirty = true;
+            self.mark_dirty();
         }
     }
 
-    /// Scroll the currently active display plane down.
+    /// Scroll the currently active display plane down by the given number of pixels.
+    ///
+    /// Scrolling by the height of the display or more clears the plane.
     pub fn scroll_down(&mut self, pixels: u8) {
         if !self.clear && pixels > 0 {
-            for y in (0..self.height - pixels).rev() {
-                for x in 0..self.width {
-                    self.display[(y + pixels) as usize][x as usize] |=
-                        self.display[y as usize][x as usize] & self.active_plane;
-                    self.display[y as usize][x as usize] &= !self.active_plane;
+            let (width, height, pixels) = self.scroll_bounds(pixels);
+            if pixels >= height {
+                self.clear_active_plane();
+                return;
+            }
+            for y in (0..height - pixels).rev() {
+                for x in 0..width {
+                    self.move_pixel((x, y), (x, y + pixels));
                 }
             }
             for y in 0..pixels {
-                for x in 0..self.width {
-                    self.display[y as usize][x as usize] &= !self.active_plane;
+                for x in 0..width {
+                    self.clear_pixel(x, y);
                 }
             }
 
-            self.dirty = true;
+            self.mark_dirty();
         }
     }
 
-    /// Scroll the currently active display plane left.
+    /// Scroll the currently active display plane left by the given number of pixels.
+    ///
+    /// SUPER-CHIP always scrolls 4 pixels, but any number is supported, for platforms that scroll
+    /// by single pixels. Scrolling by the width of the display or more clears the plane.
     pub fn scroll_left(&mut self, pixels: u8) {
         if !self.clear && pixels > 0 {
-            for y in 0..self.height {
-                for x in pixels..self.width {
-                    self.display[y as usize][(x - pixels) as usize] |=
-                        self.display[y as usize][x as usize] & self.active_plane;
-                    self.display[y as usize][x as usize] &= !self.active_plane;
+            let (width, height, pixels) = self.scroll_bounds(pixels);
+            if pixels >= width {
+                self.clear_active_plane();
+                return;
+            }
+            for y in 0..height {
+                for x in pixels..width {
+                    self.move_pixel((x, y), (x - pixels, y));
                 }
             }
-            for y in 0..self.height {
-                for x in (self.width - pixels)..self.width {
-                    self.display[y as usize][x as usize] &= !self.active_plane;
+            for y in 0..height {
+                for x in (width - pixels)..width {
+                    self.clear_pixel(x, y);
                 }
             }
 
-            self.dirty = true;
+            self.mark_dirty();
         }
     }
 
-    /// Scroll the currently active display plane right.
+    /// Scroll the currently active display plane right by the given number of pixels.
+    ///
+    /// SUPER-CHIP always scrolls 4 pixels, but any number is supported, for platforms that scroll
+    /// by single pixels. Scrolling by the width of the display or more clears the plane.
     pub fn scroll_right(&mut self, pixels: u8) {
         if !self.clear && pixels > 0 {
-            for y in 0..self.height {
-                for x in (0..self.width - pixels).rev() {
-                    self.display[y as usize][(x + pixels) as usize] |=
-                        self.display[y as usize][x as usize] & self.active_plane;
-                    self.display[y as usize][x as usize] &= !self.active_plane;
+            let (width, height, pixels) = self.scroll_bounds(pixels);
+            if pixels >= width {
+                self.clear_active_plane();
+                return;
+            }
+            for y in 0..height {
+                for x in (0..width - pixels).rev() {
+                    self.move_pixel((x, y), (x + pixels, y));
                 }
             }
-            for y in 0..self.height {
+            for y in 0..height {
                 for x in 0..pixels {
-                    self.display[y as usize][x as usize] &= !self.active_plane;
+                    self.clear_pixel(x, y);
+                }
+            }
+
+            self.mark_dirty();
+        }
+    }
+
+    fn scroll_bounds(&self, pixels: u8) -> (usize, usize, usize) {
+        (
+            usize::from(self.width),
+            usize::from(self.height),
+            usize::from(pixels),
+        )
+    }
+
+    /// Move a pixel in the currently active plane from one position to another.
+    fn move_pixel(&mut self, (x, y): (usize, usize), (to_x, to_y): (usize, usize)) {
+        let (from, to) = (self.index(x, y), self.index(to_x, to_y));
+        self.display[to] =
+            (self.display[to] & !self.active_plane) | (self.display[from] & self.active_plane);
+        self.display[from] &= !self.active_plane;
+    }
+
+    /// Unset a pixel in the currently active plane.
+    fn clear_pixel(&mut self, x: usize, y: usize) {
+        let index = self.index(x, y);
+        self.display[index] &= !self.active_plane;
+    }
+
+    /// The index of the pixel at the given coordinates in the display buffer.
+    fn index(&self, x: usize, y: usize) -> usize {
+        y * self.stride + x
+    }
+
+    /// Get the value of the pixel at the given coordinates, where each bit denotes whether the pixel
+    /// is set in the corresponding plane. Coordinates outside the current resolution wrap around,
+    /// like when drawing.
+    #[must_use]
+    pub fn get_pixel(&self, x: u8, y: u8) -> u8 {
+        let x = usize::from(x) % usize::from(self.width);
+        let y = usize::from(y) % usize::from(self.height);
+        self.display[self.index(x, y)]
+    }
+
+    /// Change the resolution of the display, for extensions like MEGA-CHIP whose resolution
+    /// exceeds SUPER-CHIP's 128x64 pixels. This reallocates and clears the display buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if either dimension is zero or greater than 256 pixels, since coordinates
+    /// beyond that can't be addressed.
+    #[must_use = "this Result must be checked for an invalid resolution"]
+    pub fn resize(&mut self, width: u16, height: u16) -> Result<(), Chip8Error> {
+        if width == 0 || height == 0 || width > 256 || height > 256 {
+            return Err(Chip8Error::InvalidResolution { width, height });
+        }
+        self.stride = usize::from(width).max(MIN_WIDTH);
+        self.display = vec![0; self.stride * usize::from(height).max(MIN_HEIGHT)];
+        self.width = width;
+        self.height = height;
+        self.mark_dirty();
+        self.clear = true;
+        Ok(())
+    }
+
+    /// Count the pixels that are lit in any plane within the current resolution.
+    #[must_use]
+    pub fn active_pixel_count(&self) -> u32 {
+        self.count_pixels(|pixel| pixel != 0)
+    }
+
+    /// Count the pixels that are lit in the given plane within the current resolution.
+    #[must_use]
+    pub fn active_pixel_count_plane(&self, plane: u8) -> u32 {
+        self.count_pixels(|pixel| pixel & plane != 0)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn count_pixels(&self, lit: impl Fn(u8) -> bool) -> u32 {
+        self.rows().flatten().filter(|pixel| lit(**pixel)).count() as u32
+    }
+
+    /// All the rows of the display buffer, [`stride`](Display::stride) pixels each, including any
+    /// pixels outside the current resolution.
+    ///
+    /// The rows can be compared directly with a two-dimensional array of pixels, like the
+    /// `[[u8; 128]; 64]` the display buffer used to be.
+    #[must_use]
+    pub fn buffer_rows(&self) -> Vec<&[u8]> {
+        self.display.chunks(self.stride).collect()
+    }
+
+    /// The rows of the display buffer, cropped to the current resolution.
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        self.display
+            .chunks(self.stride)
+            .take(usize::from(self.height))
+            .map(|row| &row[..usize::from(self.width)])
+    }
+
+    /// Extract a single plane as a grid of booleans, indexed by `[y][x]`, where `true` means the
+    /// pixel is lit in `plane`.
+    ///
+    /// Pixels outside the current resolution are always `false`. Displays that have been resized
+    /// beyond 128x64 pixels are cropped.
+    #[must_use]
+    pub fn get_plane(&self, plane: u8) -> [[bool; 128]; 64] {
+        let mut grid = [[false; 128]; 64];
+        for (grid_row, row) in grid.iter_mut().zip(self.rows()) {
+            for (cell, pixel) in grid_row.iter_mut().zip(row) {
+                *cell = pixel & plane != 0;
+            }
+        }
+        grid
+    }
+
+    /// Extract a single plane within the current resolution as packed bytes, like CHIP-8 sprite
+    /// data.
+    ///
+    /// Each row is packed 8 pixels per byte with the leftmost pixel in the most significant bit,
+    /// and padded to a whole number of bytes.
+    #[must_use]
+    pub fn get_plane_bytes(&self, plane: u8) -> Vec<u8> {
+        self.rows()
+            .flat_map(|row| {
+                row.chunks(8).map(|pixels| {
+                    pixels.iter().enumerate().fold(0, |byte, (bit, pixel)| {
+                        byte | (u8::from(pixel & plane != 0) << (7 - bit))
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// Export the pixels of the first plane, within the current resolution, as an X BitMap (XBM)
+    /// file.
+    ///
+    /// `name` is used as the prefix of the C identifiers in the file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::Display;
+    /// let xbm = Display::new().to_xbm("chip8");
+    /// assert!(xbm.starts_with("#define chip8_width 64\n#define chip8_height 32\n"));
+    /// ```
+    #[must_use]
+    pub fn to_xbm(&self, name: &str) -> String {
+        let bytes: Vec<u8> = self
+            .rows()
+            .flat_map(|row| {
+                row.chunks(8).map(|pixels| {
+                    pixels
+                        .iter()
+                        .enumerate()
+                        .fold(0, |byte, (bit, pixel)| byte | ((pixel & 1) << bit))
+                })
+            })
+            .collect();
+        let mut xbm = format!(
+            "#define {name}_width {}\n#define {name}_height {}\nstatic unsigned char {name}_bits[] = {{\n",
+            self.width, self.height
+        );
+        for (line, chunk) in bytes.chunks(12).enumerate() {
+            if line > 0 {
+                xbm.push_str(",\n");
+            }
+            let values: Vec<String> = chunk.iter().map(|byte| format!("0x{byte:02x}")).collect();
+            xbm.push_str("   ");
+            xbm.push_str(&values.join(", "));
+        }
+        xbm.push_str(" };\n");
+        xbm
+    }
+
+    /// Convert the display to a buffer of RGBA pixels, row by row within the current resolution,
+    /// with 4 bytes per pixel.
+    ///
+    /// Each pixel's color is looked up in `palette` by which planes it's set in, so `palette[3]`
+    /// is the color of pixels set in both planes, except that pixels that aren't set in any
+    /// plane get `palette[background_color]`. The colors are given as `0xRRGGBBAA`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::Display;
+    /// let mut display = Display::new();
+    /// display.draw(vec![vec![1]], 0, 0);
+    /// let rgba = display.to_rgba_buffer(&[0x000000FF, 0xFFFFFFFF, 0, 0]);
+    /// assert_eq!(rgba.len(), 64 * 32 * 4);
+    /// assert_eq!(rgba[..8], [0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0xFF]);
+    /// ```
+    #[must_use]
+    pub fn to_rgba_buffer(&self, palette: &[u32; 4]) -> Vec<u8> {
+        self.rows()
+            .flatten()
+            .flat_map(|pixel| {
+                let color = match pixel & 3 {
+                    0 => self.background_color & 3,
+                    planes => planes,
+                };
+                palette[usize::from(color)].to_be_bytes()
+            })
+            .collect()
+    }
+
+    /// Compare this display's pixels to another display's, across all planes.
+    ///
+    /// The displays are compared within the larger of their two resolutions, where pixels outside
+    /// a display's own resolution count as unset, so displays of different sizes can be compared.
+    ///
+    /// Returns an `(x, y, self_value, other_value)` tuple for each pixel that differs.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn compare(&self, other: &Display) -> Vec<(u8, u8, u8, u8)> {
+        let width = usize::from(self.width.max(other.width));
+        let height = usize::from(self.height.max(other.height));
+        let mut differences = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                let (a, b) = (self.visible_pixel(x, y), other.visible_pixel(x, y));
+                if a != b {
+                    differences.push((x as u8, y as u8, a, b));
                 }
             }
+        }
+        differences
+    }
 
-            self.dirty = true;
+    /// The pixel at the given coordinates, or 0 if they're outside the current resolution.
+    fn visible_pixel(&self, x: usize, y: usize) -> u8 {
+        if x < usize::from(self.width) && y < usize::from(self.height) {
+            self.display[self.index(x, y)]
+        } else {
+            0
         }
     }
 
+    /// Whether this display has the same resolution and pixels as another display.
+    #[must_use]
+    pub fn is_identical_to(&self, other: &Display) -> bool {
+        self.width == other.width && self.height == other.height && self.rows().eq(other.rows())
+    }
+
+    /// Assert that this display has the same resolution and pixels as another display.
+    ///
+    /// # Panics
+    ///
+    /// Panics with a list of the mismatched pixels if the displays differ.
+    #[track_caller]
+    pub fn assert_identical_to(&self, other: &Display) {
+        if self.is_identical_to(other) {
+            return;
+        }
+        let mut message = format!(
+            "Displays differ: {}x{} vs {}x{}",
+            self.width, self.height, other.width, other.height
+        );
+        for (x, y, a, b) in self.compare(other) {
+            message.push_str(&format!("\n  ({x}, {y}): {a} != {b}"));
+        }
+        panic!("{}", message);
+    }
+
     /// Change the currently active plane.
-    pub fn plane(&mut self, plane: u8) {
+    ///
+    /// `plane` is a bitmask of the selected planes, so 0 selects no planes, which makes the
+    /// `DXYN` instruction draw nothing, and 3 selects both.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Chip8Error::InvalidPlane`] if `plane` is greater than 3.
+    #[must_use = "this Result must be checked for an invalid plane"]
+    pub fn plane(&mut self, plane: u8) -> Result<(), Chip8Error> {
+        if plane > 3 {
+            return Err(Chip8Error::InvalidPlane(plane));
+        }
         self.active_plane = plane;
+        Ok(())
     }
 
     /// Switch to high-resolution mode.
-    pub fn hires(&mut self, clear: bool) {
+    ///
+    /// If `clear` is `false`, the display buffer is kept, and the pixels outside the top-left
+    /// 64x32 pixels reappear as they were before the display was switched to low-resolution
+    /// mode. The [`clear`](Display::clear) flag still tells whether the whole buffer is cleared.
+    ///
+    /// Returns `true` if the display wasn't already in this mode.
+    pub fn hires(&mut self, clear: bool) -> bool {
+        let changed = !self.hires;
         self.hires = true;
         self.width = 128;
         self.height = 64;
         if clear && !self.clear {
-            self.clear(true);
+            self.clear_all_planes();
             self.clear = true;
         }
+        changed
     }
 
     /// Switch to low-resolution mode.
-    pub fn lores(&mut self, clear: bool) {
+    ///
+    /// If `clear` is `false`, the display buffer is kept, so the top-left 64x32 pixels of a
+    /// high-resolution display are shown. Drawing only affects those pixels until the display is
+    /// switched back to high-resolution mode, and the [`clear`](Display::clear) flag still tells
+    /// whether the whole buffer is cleared.
+    ///
+    /// Returns `true` if the display wasn't already in this mode.
+    pub fn lores(&mut self, clear: bool) -> bool {
+        let changed = self.hires;
         self.hires = false;
         self.width = 64;
         self.height = 32;
         if clear && !self.clear {
-            self.clear(true);
+            self.clear_all_planes();
             self.clear = true;
         }
+        changed
+    }
+}
+
+/// A compact summary of the display state, like `[64x32 dirty plane=1]`.
+impl fmt::Display for Display {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}x{}", self.width, self.height)?;
+        if self.dirty {
+            write!(f, " dirty")?;
+        }
+        write!(f, " plane={}]", self.active_plane)
     }
 }
 
@@ -193,3 +772,37 @@ impl Default for Display {
         Self::new()
     }
 }
+
+/// A guard that restores a display's active plane when it's dropped, so that drawing into
+/// individual planes can't leave another plane active.
+pub(crate) struct PlaneGuard<'a> {
+    display: &'a mut Display,
+    plane: u8,
+}
+
+impl<'a> PlaneGuard<'a> {
+    pub(crate) fn new(display: &'a mut Display) -> Self {
+        let plane = display.active_plane;
+        PlaneGuard { display, plane }
+    }
+}
+
+impl Deref for PlaneGuard<'_> {
+    type Target = Display;
+
+    fn deref(&self) -> &Display {
+        self.display
+    }
+}
+
+impl DerefMut for PlaneGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Display {
+        self.display
+    }
+}
+
+impl Drop for PlaneGuard<'_> {
+    fn drop(&mut self) {
+        self.display.active_plane = self.plane;
+    }
+}
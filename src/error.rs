@@ -0,0 +1,163 @@
+use crate::Instruction;
+use std::fmt;
+
+/// An error that occurred while running a CHIP-8 program.
+#[derive(Debug)]
+pub enum Chip8Error {
+    /// The program executed an exit instruction, optionally with an exit code.
+    Exit(Option<u8>),
+    /// The program attempted to return from a subroutine with an empty call stack.
+    StackUnderflow {
+        /// The call stack at the time of the error, most recent first, if
+        /// [`Chip8::stack_trace_in_errors`](crate::Chip8::stack_trace_in_errors) is enabled.
+        stack_frames: Vec<u16>,
+    },
+    /// The program attempted to call a subroutine with a full call stack.
+    StackOverflow {
+        /// The call stack at the time of the error, most recent first, if
+        /// [`Chip8::stack_trace_in_errors`](crate::Chip8::stack_trace_in_errors) is enabled.
+        stack_frames: Vec<u16>,
+    },
+    /// The program attempted to call a machine code routine, which is not supported.
+    MachineCodeNotSupported,
+    /// The program attempted to select XO-CHIP bitplanes with a bitmask above 3, which would
+    /// include bitplanes that don't exist.
+    InvalidPlane(u8),
+    /// The program attempted to point I at a font character above `0xF`, which doesn't exist.
+    InvalidFontCharacter(u8),
+    /// An opcode could not be decoded into an instruction.
+    Decode(DecodeError),
+    /// The instruction isn't implemented by the interpreter or any of its extensions.
+    UnimplementedInstruction(Instruction),
+    /// The interpreter has executed as many instructions as its execution limit allows.
+    ExecutionLimitExceeded(u64),
+    /// The program has been stuck in an infinite loop.
+    InfiniteLoopDetected {
+        /// The Program Counter at the time the loop was detected.
+        pc: u16,
+    },
+    /// The display can't be resized to the requested resolution.
+    InvalidResolution {
+        /// The requested width.
+        width: u16,
+        /// The requested height.
+        height: u16,
+    },
+    /// A ROM is too large to fit in memory.
+    RomTooLarge {
+        /// The size of the ROM in bytes.
+        size: usize,
+        /// The maximum size of a ROM in bytes.
+        max_size: usize,
+    },
+    /// A ROM file couldn't be read.
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Chip8Error::Exit(Some(n)) => write!(f, "Interpreter exited with exit code {n}"),
+            Chip8Error::Exit(None) => write!(f, "Interpreter exited"),
+            Chip8Error::StackUnderflow { stack_frames } => {
+                write!(f, "Attempted pop from empty stack")?;
+                write_stack_frames(f, stack_frames)
+            }
+            Chip8Error::StackOverflow { stack_frames } => {
+                write!(f, "Stack limit exceeded")?;
+                write_stack_frames(f, stack_frames)
+            }
+            Chip8Error::MachineCodeNotSupported => write!(f, "Machine code is not supported"),
+            Chip8Error::InvalidPlane(n) => write!(
+                f,
+                "Plane bitmask must be 0-3 (supporting up to 2 bitplanes), got {n}"
+            ),
+            Chip8Error::InvalidFontCharacter(n) => {
+                write!(
+                    f,
+                    "Attempted to select font character {n:#04X}, which doesn't exist"
+                )
+            }
+            Chip8Error::Decode(e) => write!(f, "{e}"),
+            Chip8Error::UnimplementedInstruction(instruction) => {
+                write!(f, "Unimplemented instruction {instruction:?}")
+            }
+            Chip8Error::ExecutionLimitExceeded(limit) => {
+                write!(f, "Execution limit of {limit} instructions exceeded")
+            }
+            Chip8Error::InfiniteLoopDetected { pc } => {
+                write!(f, "Infinite loop detected at PC {pc:#06X}")
+            }
+            Chip8Error::InvalidResolution { width, height } => {
+                write!(f, "Invalid display resolution {width}x{height}")
+            }
+            Chip8Error::RomTooLarge { size, max_size } => {
+                write!(
+                    f,
+                    "ROM of {size} bytes exceeds the maximum size of {max_size} bytes"
+                )
+            }
+            #[cfg(feature = "std")]
+            Chip8Error::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for Chip8Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Chip8Error::Decode(e) => Some(e),
+            #[cfg(feature = "std")]
+            Chip8Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<DecodeError> for Chip8Error {
+    fn from(e: DecodeError) -> Self {
+        Chip8Error::Decode(e)
+    }
+}
+
+/// An error that occurred while decoding an opcode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The opcode doesn't map to any instruction.
+    UnknownOpcode {
+        /// The opcode.
+        opcode: u16,
+        /// The address the opcode was read from.
+        pc: u16,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnknownOpcode { opcode, pc } => {
+                write!(f, "Unknown opcode {opcode:04X} at PC {pc:#06X}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Chip8Error {
+    fn from(e: std::io::Error) -> Self {
+        Chip8Error::Io(e)
+    }
+}
+
+fn write_stack_frames(f: &mut fmt::Formatter<'_>, stack_frames: &[u16]) -> fmt::Result {
+    if !stack_frames.is_empty() {
+        write!(f, ", stack trace:")?;
+        for frame in stack_frames {
+            write!(f, " {frame:#06X}")?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,88 @@
+/// A font for the hexadecimal digits 0 through F.
+///
+/// This wraps [`octopt::FontStyle`] and adds fonts that `octopt` doesn't provide. Load one into
+/// an interpreter with [`Chip8::load_font`](crate::Chip8::load_font).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontStyle {
+    /// A font provided by `octopt`.
+    Octopt(octopt::FontStyle),
+    /// The font used by the CHIP-8X interpreter, which ran on the COSMAC VIP.
+    Chip8X,
+    /// The font used by the DREAM 6800.
+    Dream6800,
+}
+
+const CHIP8X_FONT: [u8; 80] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+    0x60, 0x20, 0x20, 0x20, 0x70, // 1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+    0xF0, 0x10, 0x70, 0x10, 0xF0, // 3
+    0xA0, 0xA0, 0xF0, 0x20, 0x20, // 4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+    0xF0, 0x10, 0x10, 0x10, 0x10, // 7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+    0xF0, 0x50, 0x70, 0x50, 0xF0, // B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+    0xF0, 0x50, 0x50, 0x50, 0xF0, // D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+];
+
+const DREAM6800_FONT: [u8; 80] = [
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // 0
+    0x40, 0x40, 0x40, 0x40, 0x40, // 1
+    0xE0, 0x20, 0xE0, 0x80, 0xE0, // 2
+    0xE0, 0x20, 0xE0, 0x20, 0xE0, // 3
+    0x80, 0xA0, 0xA0, 0xE0, 0x20, // 4
+    0xE0, 0x80, 0xE0, 0x20, 0xE0, // 5
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0, // 6
+    0xE0, 0x20, 0x20, 0x20, 0x20, // 7
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // 8
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0, // 9
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // A
+    0xC0, 0xA0, 0xE0, 0xA0, 0xC0, // B
+    0xE0, 0x80, 0x80, 0x80, 0xE0, // C
+    0xC0, 0xA0, 0xA0, 0xA0, 0xC0, // D
+    0xE0, 0x80, 0xE0, 0x80, 0xE0, // E
+    0xE0, 0x80, 0xC0, 0x80, 0x80, // F
+];
+
+impl FontStyle {
+    /// Every available font style.
+    #[must_use]
+    pub fn all() -> &'static [FontStyle] {
+        &[
+            FontStyle::Octopt(octopt::FontStyle::Octo),
+            FontStyle::Octopt(octopt::FontStyle::Vip),
+            FontStyle::Octopt(octopt::FontStyle::Schip),
+            FontStyle::Chip8X,
+            FontStyle::Dream6800,
+        ]
+    }
+
+    /// The small font data, 5 bytes per character, and the big font data, 10 bytes per
+    /// character, if the font style has one.
+    #[must_use]
+    pub fn get_font_data(&self) -> ([u8; 80], Option<[u8; 160]>) {
+        match self {
+            FontStyle::Octopt(font_style) => font_style.get_font_data(),
+            FontStyle::Chip8X => (CHIP8X_FONT, None),
+            FontStyle::Dream6800 => (DREAM6800_FONT, None),
+        }
+    }
+}
+
+impl Default for FontStyle {
+    fn default() -> Self {
+        FontStyle::Octopt(octopt::FontStyle::default())
+    }
+}
+
+impl From<octopt::FontStyle> for FontStyle {
+    fn from(font_style: octopt::FontStyle) -> Self {
+        FontStyle::Octopt(font_style)
+    }
+}
@@ -0,0 +1,57 @@
+use crate::{Chip8, Instruction};
+use arbitrary::{Arbitrary, Unstructured};
+
+/// An arbitrary interpreter with the default options, for fuzzing and property-based testing.
+///
+/// The state is structurally valid: the Program Counter points at a decodable opcode at or above
+/// `0x200`, the Stack Pointer is within the call stack, and the font is in place. The ROM is
+/// loaded from the start address, so everything else in memory is zero.
+impl<'a> Arbitrary<'a> for Chip8 {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut chip8 = Chip8::default();
+        let max_size = usize::from(u16::MAX - chip8.rom_start_address()) + 1;
+        let rom_size = u.int_in_range(0..=max_size)?;
+        let mut rom = vec![0; rom_size];
+        u.fill_buffer(&mut rom)?;
+        chip8.read_rom(&rom);
+
+        chip8.pc = u.int_in_range(0x200..=0xFFFE)?;
+        let opcode = u16::arbitrary(u)?;
+        let opcode = if Instruction::try_from(opcode).is_ok() {
+            opcode
+        } else {
+            0x00E0
+        };
+        let [high, low] = opcode.to_be_bytes();
+        chip8.memory[usize::from(chip8.pc)] = high;
+        chip8.memory[usize::from(chip8.pc) + 1] = low;
+
+        chip8.sp = u.int_in_range(0..=chip8.stack.len())?;
+        for address in &mut chip8.stack[..chip8.sp] {
+            *address = u16::arbitrary(u)?;
+        }
+        chip8.v = Arbitrary::arbitrary(u)?;
+        chip8.i = u16::arbitrary(u)?;
+        chip8.delay = u8::arbitrary(u)?;
+        chip8.sound = u8::arbitrary(u)?;
+        chip8.keyboard = Arbitrary::arbitrary(u)?;
+        Ok(chip8)
+    }
+}
+
+/// An arbitrary instruction, for fuzzing [`Chip8::execute`].
+///
+/// [`Instruction`] is defined in `decasm`, so it can't implement [`Arbitrary`] here. Instead, an
+/// arbitrary opcode is decoded, and opcodes that don't map to an instruction are replaced with
+/// [`Instruction::Clear`]. An [`Instruction::SetIndexLong`] is given an arbitrary address.
+///
+/// # Errors
+///
+/// Returns `Err` if `u` can't provide the data, like [`Arbitrary::arbitrary`].
+pub fn arbitrary_instruction(u: &mut Unstructured<'_>) -> arbitrary::Result<Instruction> {
+    Ok(match Instruction::try_from(u16::arbitrary(u)?) {
+        Ok(Instruction::SetIndexLong) => Instruction::SetIndex(u16::arbitrary(u)?),
+        Ok(instruction) => instruction,
+        Err(_) => Instruction::Clear,
+    })
+}
@@ -4,25 +4,64 @@
 pub use decasm;
 pub use decasm::Instruction;
 use decasm::{Byte, Register};
+pub use fastrand;
 use itertools::Either;
 use octopt::LoResDxy0Behavior;
 pub use octopt::{Options, Quirks};
+use std::collections::VecDeque;
 
 mod display;
-pub use display::Display;
+use display::PlaneGuard;
+pub use display::{Display, DrawFlags};
 
-use ux::u4;
+mod error;
+pub use error::{Chip8Error, DecodeError};
+
+mod font;
+pub use font::FontStyle;
+
+mod analysis;
+pub use analysis::{AffectedRegisters, InstructionRegisters};
+
+mod memory;
+pub use memory::{MemoryModel, MemoryStats, MemoryView};
+
+mod diff;
+pub use diff::Chip8Diff;
+
+#[cfg(feature = "std")]
+mod disassembly;
+
+mod registers;
+pub use registers::{RegisterView, RegisterViewMut};
+
+#[cfg(feature = "arbitrary")]
+mod fuzz;
+#[cfg(feature = "arbitrary")]
+pub use fuzz::arbitrary_instruction;
+
+pub use ux::u4;
 
 /// A struct for holding the state of the CHIP-8 interpreter.
+///
+/// `Chip8` is [`Send`], so an interpreter can be moved to another thread, but it isn't [`Sync`]:
+/// running it mutates its state, and its [`extensions`](Chip8::extensions) are only required to
+/// be `Send`. Wrap it in a `Mutex` to share it between threads.
 pub struct Chip8 {
-    /// The Program Counter, which contains the index in [`memory`] that's currently executed.
+    /// The Program Counter, which contains the address in memory that's currently executed.
     pub pc: u16,
-    /// The Stack Pointer, which contains the index in [`stack`] that's currently the top of the stack.
+    /// The Stack Pointer, which contains the number of return addresses currently on the
+    /// [`stack`].
+    ///
+    /// The oldest return address is at `stack[0]` and the most recent at `stack[sp - 1]`. Earlier
+    /// versions left `stack[0]` unused and kept the most recent return address at `stack[sp]`.
     pub sp: usize,
-    /// The call stack.
-    pub stack: [u16; 16],
-    /// The CHIP-8 memory
-    pub memory: [u8; 65536],
+    /// The call stack. Its length is the maximum number of nested subroutine calls, which is 16
+    /// by default; use [`with_stack_size`](Chip8::with_stack_size) to change it.
+    pub stack: Vec<u16>,
+    /// The CHIP-8 memory. Read it with [`memory_view`](Chip8::memory_view), and write it by
+    /// indexing the interpreter, like `chip8[0x300u16] = 0x42`.
+    pub(crate) memory: MemoryModel,
     /// CHIP-8's index register.
     pub i: u16,
     /// CHIP-8's variable registers.
@@ -30,9 +69,15 @@ pub struct Chip8 {
     /// SCHIP's user flags.
     pub flags: [u8; 16],
     /// The delay timer. If non-zero, this should count down at 60 Hz until it reaches zero.
+    ///
+    /// This field will be made private in a future version; use
+    /// [`delay_timer`](Chip8::delay_timer) and [`set_delay_timer`](Chip8::set_delay_timer).
     pub delay: u8,
     /// The sound timer. If non-zero, this should count down at 60 Hz until it reaches zero. While it is
     /// non-zero, an audible sound or visual indication should be present.
+    ///
+    /// This field will be made private in a future version; use
+    /// [`sound_timer`](Chip8::sound_timer) and [`set_sound_timer`](Chip8::set_sound_timer).
     pub sound: u8,
     /// CHIP-8's display buffer.
     pub display: Display,
@@ -40,27 +85,264 @@ pub struct Chip8 {
     pub options: Options,
     /// The current state of the CHIP-8 hexadecimal keypad.
     pub keyboard: [bool; 16],
+    /// Extensions that are consulted, in order, for any instruction the interpreter doesn't
+    /// implement itself.
+    pub extensions: Vec<Box<dyn Chip8Extension>>,
+    /// A callback for when the timers are decremented, set with
+    /// [`set_timer_callback`](Chip8::set_timer_callback).
+    timer_callback: Option<Box<dyn FnMut(u8, u8) + Send>>,
+    /// The value of the sound timer before the timers were last ticked.
+    prev_sound: u8,
+    /// The value of the sound timer after the timers were last ticked.
+    ticked_sound: u8,
+    /// The length in bytes of the most recently loaded CHIP-8 program.
+    pub rom_length: usize,
+    /// Whether stack errors should include the contents of the call stack. This is useful for
+    /// debugging runaway recursion, and costs nothing when disabled.
+    pub stack_trace_in_errors: bool,
+    /// The number of instructions executed since this interpreter was created.
+    pub cycles: u64,
+    /// The maximum number of instructions to execute. Useful for guaranteeing the termination of
+    /// test harnesses and fuzz targets.
+    pub execution_limit: Option<u64>,
+    /// The VF conflict quirk, which isn't part of [`Quirks`] yet. If `true` or `None`, arithmetic
+    /// instructions write VF after their result, so VF always holds the flag afterwards. If
+    /// `false`, they write VF before their result, which matters when VF is one of the operands.
+    pub vf_conflict_quirk: Option<bool>,
+    /// Whether [`run`](Chip8::run) should detect when the program is stuck in an infinite loop.
+    pub loop_detection: bool,
+    /// The number of consecutive frames the program must be stuck in a loop before it's detected.
+    pub loop_detection_threshold: u32,
+    /// The states the interpreter was in at the end of the most recent frames.
+    loop_history: VecDeque<LoopState>,
+    /// The number of consecutive frames that ended in a state that was already in the history.
+    loop_repeats: u32,
+    /// Whether the program has read the keypad since the last check for infinite loops.
+    keyboard_read: bool,
+    /// The number of times [`run`](Chip8::run) has stopped early because of the `vblank` quirk.
+    pub vblank_count: u64,
+    /// The number of [`Draw`](Instruction::Draw) instructions that have been executed.
+    ///
+    /// This differs from the display's [`total_draw_calls`](Display::total_draw_calls), which
+    /// counts each plane separately: an XO-CHIP sprite drawn to both planes counts once here and
+    /// twice there, and one drawn while no plane is selected only counts here.
+    pub draw_calls: u64,
+    /// Whether memory is included when hashing and comparing interpreters. Otherwise only the
+    /// registers, timers, stack pointer and display buffer are.
+    pub hash_memory: bool,
+    /// A quirk for which key `FX0A` picks when several keys are held down. By default, like
+    /// SUPER-CHIP, it's the first one that's detected, which is the lowest-numbered one. If
+    /// `true`, it's the highest-numbered one.
+    pub block_key_highest: Option<bool>,
+    /// A quirk for font characters above `0xF`, which don't exist. If `true` or `None`, like the
+    /// COSMAC VIP, [`FontCharacter`](Instruction::FontCharacter) and
+    /// [`BigFontCharacter`](Instruction::BigFontCharacter) only use the lowest nibble of VX. If
+    /// `false`, they fail with [`Chip8Error::InvalidFontCharacter`].
+    pub font_character_mask_quirk: Option<bool>,
+    /// The highest address an opcode has been fetched from, if any.
+    pub pc_high_watermark: Option<u16>,
+    /// The address the most recent opcode was fetched from, which decoding errors report. Before
+    /// anything is fetched, this is the start address.
+    fetch_address: u16,
+    /// The random number generator used by [`Instruction::Random`]. Give it a fixed seed with
+    /// [`fastrand::Rng::seed`] for deterministic runs. It isn't part of a
+    /// [`snapshot`](Chip8::snapshot).
+    pub rng: fastrand::Rng,
+    /// The addresses that have been fetched as part of an instruction, if coverage tracking is
+    /// enabled with [`enable_coverage`](Chip8::enable_coverage).
+    coverage: Option<Box<[bool; 65536]>>,
+    /// Snapshots taken before the most recently executed instructions, for
+    /// [`step_back`](Chip8::step_back), with the most recent one last.
+    reverse_history: VecDeque<Chip8Snapshot>,
+    /// The maximum number of snapshots in the reverse history. If 0, reverse stepping is
+    /// disabled.
+    reverse_history_len: usize,
+    /// An interrupt vector that [`run`](Chip8::run) should jump to, like with
+    /// [`interrupt`](Chip8::interrupt), before executing the next frame's first instruction. It's
+    /// reset to `None` when the interrupt is handled.
+    pub interrupt_pending: Option<u16>,
 }
 
-impl Chip8 {
-    /// Create a new CHIP-8 interpreter with the given [`octopt::Options`].
-    #[must_use]
-    pub fn new(options: Options) -> Chip8 {
-        let mut memory = [0; 65536];
+/// The number of frames of state history that is checked for infinite loops.
+const LOOP_HISTORY_LENGTH: usize = 16;
 
-        let (font, big_font) = &options.font_style.get_font_data();
+/// The default maximum number of nested subroutine calls.
+const DEFAULT_STACK_SIZE: usize = 16;
 
-        memory[0x50..(0x50 + font.len())].clone_from_slice(&font[..]);
+/// The address where the font is loaded.
+const FONT_ADDRESS: u16 = 0x50;
 
-        if let Some(big_font) = big_font {
-            memory[(0x50 + font.len())..(0x50 + font.len() + big_font.len())]
-                .clone_from_slice(&big_font[..]);
-        }
+/// How far before the end of memory the font is loaded if a CHIP-8 program starts too low to fit
+/// it at [`FONT_ADDRESS`]. This is `0xFF00` with 64 KB of memory, and `0xF00` with 4 KB.
+const HIGH_FONT_OFFSET: usize = 0x100;
+
+/// The combined length in bytes of the small and big fonts.
+const FONT_LENGTH: u16 = 80 + 160;
+
+/// The address where the COSMAC VIP interpreter's call stack starts.
+const VIP_STACK_START: usize = 0xEA0;
+
+/// The part of the interpreter's state that must change for a program not to be stuck in a loop.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct LoopState {
+    pc: u16,
+    i: u16,
+    sp: usize,
+    v: [u8; 16],
+    delay: u8,
+    sound: u8,
+}
+
+/// A copy of the state of a CHIP-8 interpreter, for save states and rewinding.
+///
+/// Created with [`Chip8::snapshot`], updated in place with [`Chip8::snapshot_into`] and restored
+/// with [`Chip8::restore`]. This includes the
+/// machine state, but not the interpreter's [`extensions`](Chip8::extensions) and its
+/// [`rng`](Chip8::rng), or state that's only used for analysis: the
+/// [coverage map](Chip8::coverage_map), the [`pc_high_watermark`](Chip8::pc_high_watermark) and
+/// the draw and VBlank counters. Restoring a snapshot leaves those as they are, so stepping back
+/// doesn't forget which code has run.
+#[derive(Clone)]
+pub struct Chip8Snapshot {
+    pc: u16,
+    sp: usize,
+    stack: Vec<u16>,
+    memory: MemoryModel,
+    i: u16,
+    v: [u8; 16],
+    flags: [u8; 16],
+    delay: u8,
+    sound: u8,
+    display: Box<Display>,
+    options: Options,
+    keyboard: [bool; 16],
+    prev_sound: u8,
+    ticked_sound: u8,
+    rom_length: usize,
+    stack_trace_in_errors: bool,
+    cycles: u64,
+    execution_limit: Option<u64>,
+    vf_conflict_quirk: Option<bool>,
+    loop_detection: bool,
+    loop_detection_threshold: u32,
+    loop_history: VecDeque<LoopState>,
+    loop_repeats: u32,
+    keyboard_read: bool,
+    hash_memory: bool,
+    block_key_highest: Option<bool>,
+    font_character_mask_quirk: Option<bool>,
+    fetch_address: u16,
+    interrupt_pending: Option<u16>,
+}
+
+/// A change in whether sound should be playing, as reported by [`Chip8::sound_edge`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundEdge {
+    /// The sound timer went from zero to non-zero; sound should start playing.
+    Started,
+    /// The sound timer went from non-zero to zero; sound should stop playing.
+    Stopped,
+    /// Sound is still playing, or still not playing.
+    Unchanged,
+}
+
+/// A CHIP-8 platform variant, which can be converted into [`Options`] with its quirks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    /// The original CHIP-8 interpreter for the COSMAC VIP.
+    Chip8,
+    /// SUPER-CHIP for the HP 48 calculators.
+    SuperChip,
+    /// XO-CHIP, as implemented by Octo.
+    XoChip,
+}
+
+impl From<Platform> for Options {
+    fn from(platform: Platform) -> Self {
+        Options::new(match platform {
+            Platform::Chip8 => octopt::Platform::Vip,
+            Platform::SuperChip => octopt::Platform::Schip,
+            Platform::XoChip => octopt::Platform::Xochip,
+        })
+    }
+}
+
+/// Information about a successful call to [`Chip8::run`] or [`Chip8::run_until_display_draw`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunResult {
+    /// The number of instructions that were executed, which might be fewer than the requested
+    /// tickrate.
+    pub instructions_executed: u16,
+    /// Why the interpreter stopped executing instructions.
+    pub stop_reason: StopReason,
+}
+
+/// Why the interpreter stopped executing instructions, as reported by a [`RunResult`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StopReason {
+    /// As many instructions as requested were executed.
+    #[default]
+    TickLimit,
+    /// A sprite was drawn and the `vblank` quirk is enabled, so the interpreter waited for the
+    /// next frame.
+    VBlank,
+    /// The display was changed.
+    DisplayDrawn,
+}
+
+/// An event reported by [`Chip8::tick_timers_with_callback`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerEvent {
+    /// The sound timer was set to a non-zero value; sound should start playing.
+    SoundStarted,
+    /// The sound timer reached zero; sound should stop playing.
+    SoundStopped,
+    /// The delay timer reached zero.
+    DelayReachedZero,
+}
+
+/// A trait for extending the interpreter with instructions it doesn't implement itself, such as
+/// those of other CHIP-8 variants.
+///
+/// Extensions must be [`Send`], so that the interpreter can be moved between threads.
+pub trait Chip8Extension: Send {
+    /// Execute a CHIP-8 `[Instruction]` on the given interpreter.
+    ///
+    /// Returns `None` if this extension doesn't handle the instruction, in which case it's passed
+    /// on to the next extension.
+    fn execute(
+        &mut self,
+        chip8: &mut Chip8,
+        instruction: Instruction,
+    ) -> Option<Result<(), Chip8Error>>;
+}
+
+impl Chip8 {
+    /// Create a new CHIP-8 interpreter with the given [`octopt::Options`], or anything that can be
+    /// converted into them, like a [`Platform`].
+    ///
+    /// This might be deprecated in the future in favor of [`Chip8::with_options`] or converting
+    /// the [`Options`] with [`Into`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::{Chip8, Platform};
+    /// let chip8 = Chip8::new(Platform::SuperChip);
+    /// assert_eq!(chip8.options.quirks.shift, Some(true));
+    /// ```
+    #[must_use]
+    pub fn new(options: impl Into<Options>) -> Chip8 {
+        let options = options.into();
+        let start_address = options.start_address.unwrap_or(0x200);
+        let mut memory = MemoryModel::default();
+        let address = font_address(start_address, memory.len());
+        write_font(&mut memory, address, options.font_style.into());
 
         Chip8 {
-            pc: options.start_address.unwrap_or(0x200),
+            pc: start_address,
             sp: 0,
-            stack: [0; 16],
+            stack: vec![0; DEFAULT_STACK_SIZE],
             memory,
             i: 0,
             v: [0; 16],
@@ -70,7 +352,120 @@ impl Chip8 {
             display: Display::new(),
             options,
             keyboard: [false; 16],
+            extensions: Vec::new(),
+            timer_callback: None,
+            prev_sound: 0,
+            ticked_sound: 0,
+            rom_length: 0,
+            stack_trace_in_errors: false,
+            cycles: 0,
+            execution_limit: None,
+            vf_conflict_quirk: None,
+            loop_detection: false,
+            loop_detection_threshold: 60,
+            loop_history: VecDeque::with_capacity(LOOP_HISTORY_LENGTH),
+            loop_repeats: 0,
+            keyboard_read: false,
+            vblank_count: 0,
+            draw_calls: 0,
+            hash_memory: false,
+            block_key_highest: None,
+            font_character_mask_quirk: None,
+            pc_high_watermark: None,
+            fetch_address: start_address,
+            rng: fastrand::Rng::new(),
+            coverage: None,
+            reverse_history: VecDeque::new(),
+            reverse_history_len: 0,
+            interrupt_pending: None,
+        }
+    }
+
+    /// Create a new CHIP-8 interpreter with the given [`octopt::Options`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::{Chip8, Options};
+    /// let chip8 = Chip8::with_options(Options::default());
+    /// ```
+    #[must_use]
+    pub fn with_options(options: Options) -> Chip8 {
+        Self::new(options)
+    }
+
+    /// Limit the total number of instructions this interpreter will execute.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::Chip8;
+    /// let chip8 = Chip8::default().with_execution_limit(1000);
+    /// assert_eq!(chip8.execution_limit, Some(1000));
+    /// ```
+    #[must_use]
+    pub fn with_execution_limit(mut self, limit: u64) -> Self {
+        self.execution_limit = Some(limit);
+        self
+    }
+
+    /// Change the maximum number of nested subroutine calls. This empties the call stack.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::Chip8;
+    /// let chip8 = Chip8::default().with_stack_size(32);
+    /// assert_eq!(chip8.stack.len(), 32);
+    /// ```
+    #[must_use]
+    pub fn with_stack_size(mut self, size: usize) -> Self {
+        self.stack = vec![0; size];
+        self.sp = 0;
+        self
+    }
+
+    /// Fill memory with random bytes from [`rng`](Chip8::rng), like the uninitialized RAM of
+    /// the COSMAC VIP, which some programs rely on. The font is kept, so this should be done
+    /// before a ROM is loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::Chip8;
+    /// let chip8 = Chip8::default().with_random_memory();
+    /// assert!(chip8.memory_dump()[0x200..].iter().any(|&byte| byte != 0));
+    /// ```
+    #[must_use]
+    pub fn with_random_memory(mut self) -> Self {
+        let font_address = usize::from(self.font_address());
+        let font_region = font_address..font_address + usize::from(FONT_LENGTH);
+        let font = self.memory[font_region.clone()].to_vec();
+        for byte in self.memory.iter_mut() {
+            *byte = self.rng.u8(..);
         }
+        self.memory[font_region].copy_from_slice(&font);
+        self
+    }
+
+    /// Replace the memory, for example with [`MemoryModel::small`] for strict compatibility
+    /// with the 4 KB of the original CHIP-8. The font is loaded into the new memory, which
+    /// should be done before a ROM is loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::{Chip8, MemoryModel};
+    /// let chip8 = Chip8::default().with_memory_model(MemoryModel::small());
+    /// assert_eq!(chip8.memory_dump().len(), 4096);
+    /// ```
+    #[must_use]
+    pub fn with_memory_model(mut self, memory: MemoryModel) -> Self {
+        self.memory = memory;
+        self.rom_length = 0;
+        let address = self.font_address();
+        write_font(&mut self.memory, address, self.options.font_style.into());
+        self
     }
 
     /// Change quirk settings
@@ -78,9 +473,293 @@ impl Chip8 {
         self.options.quirks = quirks;
     }
 
-    /// Read CHIP-8 program ("ROM") into memory
-    pub fn read_rom(&mut self, rom: &[u8]) {
-        self.memory[0x200..][..rom.len()].copy_from_slice(rom);
+    /// Change individual quirk settings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::Chip8;
+    /// let mut chip8 = Chip8::default();
+    /// chip8.set_quirk(|quirks| quirks.shift = Some(true));
+    /// assert_eq!(chip8.options.quirks.shift, Some(true));
+    /// ```
+    pub fn set_quirk<F: FnOnce(&mut Quirks)>(&mut self, f: F) {
+        f(&mut self.options.quirks);
+    }
+
+    /// Change the shift quirk setting.
+    pub fn set_shift_quirk(&mut self, enabled: bool) {
+        self.options.quirks.shift = Some(enabled);
+    }
+
+    /// Change the load/store quirk setting.
+    ///
+    /// If `true`, `FX55` and `FX65` leave the index register unchanged. If the quirk is `None`, it
+    /// behaves as if it's `false`, both here and when the program toggles it with
+    /// [`Instruction::ToggleLoadStoreQuirk`].
+    pub fn set_load_store_quirk(&mut self, enabled: bool) {
+        self.options.quirks.load_store = Some(enabled);
+    }
+
+    /// Change the jump0 quirk setting.
+    pub fn set_jump0_quirk(&mut self, enabled: bool) {
+        self.options.quirks.jump0 = Some(enabled);
+    }
+
+    /// Change the VBlank quirk setting.
+    pub fn set_vblank_quirk(&mut self, enabled: bool) {
+        self.options.quirks.vblank = Some(enabled);
+    }
+
+    /// Change the delay wrap quirk setting.
+    pub fn set_delay_wrap_quirk(&mut self, enabled: bool) {
+        self.options.quirks.delay_wrap = Some(enabled);
+    }
+
+    /// Change the resolution clear quirk setting.
+    pub fn set_res_clear_quirk(&mut self, enabled: bool) {
+        self.options.quirks.res_clear = Some(enabled);
+    }
+
+    /// Change how `DXY0` behaves in low-resolution mode.
+    pub fn set_lores_dxy0_quirk(&mut self, behavior: LoResDxy0Behavior) {
+        self.options.quirks.lores_dxy0 = Some(behavior);
+    }
+
+    /// Read CHIP-8 program ("ROM") into memory, at the
+    /// [start address](Chip8::rom_start_address).
+    ///
+    /// Any part of a previously loaded ROM that the new one doesn't overwrite is zeroed, so that
+    /// it can't be executed by accident.
+    ///
+    /// A ROM that doesn't fit in memory is cut off at the end of memory. Returns the number of
+    /// bytes that were loaded. Use [`warm_load`](Chip8::warm_load) to get a
+    /// [`Chip8Error::RomTooLarge`] instead.
+    pub fn read_rom(&mut self, rom: &[u8]) -> usize {
+        let start = (self.rom_start_address() as usize).min(self.memory.len());
+        let available = self.memory.len() - start;
+        let rom = &rom[..rom.len().min(available)];
+        self.memory[start..][..self.rom_length.min(available)].fill(0);
+        self.memory[start..][..rom.len()].copy_from_slice(rom);
+        self.rom_length = rom.len();
+        self.rom_length
+    }
+
+    /// Load a font into memory, replacing the one given by the options.
+    ///
+    /// The small font is loaded at `0x50`, or 256 bytes before the end of memory if the start
+    /// address is so low that the program would overlap it, and the big font, if the font style has one, right after
+    /// it. A font style without a big font leaves the current big font in place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::{Chip8, FontStyle};
+    /// let mut chip8 = Chip8::default();
+    /// chip8.load_font(FontStyle::Dream6800);
+    /// assert_eq!(chip8[0x50], 0xE0);
+    /// ```
+    pub fn load_font(&mut self, font_style: impl Into<FontStyle>) {
+        let address = self.font_address();
+        write_font(&mut self.memory, address, font_style.into());
+    }
+
+    /// Read a CHIP-8 program ("ROM") from a file into memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Chip8Error::Io`] if the file can't be read, or [`Chip8Error::RomTooLarge`] if
+    /// it doesn't fit in memory.
+    #[cfg(feature = "std")]
+    #[must_use = "this Result must be checked for ROM loading errors"]
+    pub fn load_rom_file<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), Chip8Error> {
+        let rom = std::fs::read(path)?;
+        self.check_rom_size(&rom)?;
+        self.read_rom(&rom);
+        Ok(())
+    }
+
+    /// Replace the running CHIP-8 program ("ROM") with another one, without resetting the whole
+    /// interpreter.
+    ///
+    /// The ROM is read into memory like with [`read_rom`](Chip8::read_rom), and execution
+    /// restarts at the start address with cleared registers, stack and timers. The SUPER-CHIP
+    /// user flags, the keyboard state, the display and the options are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Chip8Error::RomTooLarge`] if the ROM doesn't fit in memory, in which case the
+    /// interpreter is left unchanged.
+    #[must_use = "this Result must be checked for ROM loading errors"]
+    pub fn warm_load(&mut self, rom: &[u8]) -> Result<(), Chip8Error> {
+        self.check_rom_size(rom)?;
+        self.read_rom(rom);
+        self.pc = self.rom_start_address();
+        self.fetch_address = self.pc;
+        self.v = [0; 16];
+        self.i = 0;
+        self.stack.fill(0);
+        self.sp = 0;
+        self.delay = 0;
+        self.sound = 0;
+        Ok(())
+    }
+
+    /// Create a new CHIP-8 interpreter with the default options and load a CHIP-8 program
+    /// ("ROM") into it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Chip8Error::RomTooLarge`] if the ROM doesn't fit in memory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::Chip8;
+    /// let chip8 = Chip8::load_rom_bytes(&[0x00, 0xE0]).unwrap();
+    /// assert_eq!(chip8.rom_length, 2);
+    /// ```
+    #[must_use = "this Result must be checked for ROM loading errors"]
+    pub fn load_rom_bytes(rom: &[u8]) -> Result<Chip8, Chip8Error> {
+        let mut chip8 = Chip8::default();
+        chip8.check_rom_size(rom)?;
+        chip8.read_rom(rom);
+        Ok(chip8)
+    }
+
+    /// Create a new CHIP-8 interpreter with the given options, load a CHIP-8 program ("ROM") into
+    /// it, and [`run`](Chip8::run) it for the given number of frames.
+    ///
+    /// Returns the interpreter in its final state, for inspecting it afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Chip8Error::RomTooLarge`] if the ROM doesn't fit in memory, or `Err` if a runtime
+    /// CHIP-8 error occurs during execution.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::{Chip8, Options};
+    /// // 0x200: v0 += 1; 0x202: jump 0x200
+    /// let chip8 = Chip8::run_rom(&[0x70, 0x01, 0x12, 0x00], Options::default(), 10, 2).unwrap();
+    /// assert_eq!(chip8.v[0], 10);
+    /// ```
+    #[must_use = "this Result must be checked for CHIP-8 execution errors"]
+    pub fn run_rom(
+        rom: &[u8],
+        options: Options,
+        tickrate: u16,
+        frames: u32,
+    ) -> Result<Chip8, Chip8Error> {
+        let mut chip8 = Chip8::new(options);
+        chip8.check_rom_size(rom)?;
+        chip8.read_rom(rom);
+        for _ in 0..frames {
+            chip8.run(tickrate)?;
+        }
+        Ok(chip8)
+    }
+
+    /// Check that a ROM fits in the memory after the address it's loaded at.
+    fn check_rom_size(&self, rom: &[u8]) -> Result<(), Chip8Error> {
+        let max_size = self
+            .memory
+            .len()
+            .saturating_sub(self.rom_start_address() as usize);
+        if rom.len() > max_size {
+            return Err(Chip8Error::RomTooLarge {
+                size: rom.len(),
+                max_size,
+            });
+        }
+        Ok(())
+    }
+
+    /// The address where execution of the CHIP-8 program starts.
+    #[must_use]
+    pub fn rom_start_address(&self) -> u16 {
+        self.options.start_address.unwrap_or(0x200)
+    }
+
+    /// The address where the font is loaded, which
+    /// [`FontCharacter`](Instruction::FontCharacter) points into.
+    fn font_address(&self) -> u16 {
+        font_address(self.rom_start_address(), self.memory.len())
+    }
+
+    /// The address just past the end of the loaded CHIP-8 program.
+    ///
+    /// This is a `usize`, since a program that fills the rest of 64 KB of memory ends at
+    /// `0x10000`, which doesn't fit in a `u16`.
+    #[must_use]
+    pub fn effective_memory_end(&self) -> usize {
+        usize::from(self.rom_start_address()) + self.rom_length
+    }
+
+    /// An estimate of the size of the executable part of the CHIP-8 program, from the start
+    /// address up to and including the highest opcode that has been fetched.
+    ///
+    /// Returns `None` if no opcodes at or after the start address have been fetched yet.
+    #[must_use]
+    pub fn program_size_estimate(&self) -> Option<usize> {
+        let size = self
+            .pc_high_watermark?
+            .checked_sub(self.rom_start_address())?;
+        Some(usize::from(size) + 2)
+    }
+
+    /// The number of bytes of memory after the end of the loaded CHIP-8 program.
+    #[must_use]
+    pub fn available_memory(&self) -> usize {
+        self.memory
+            .len()
+            .saturating_sub(self.rom_start_address() as usize + self.rom_length)
+    }
+
+    /// Whether the loaded CHIP-8 program reaches into the memory area the original COSMAC VIP
+    /// interpreter used for its call stack and display buffer, at `0xEA0` and above.
+    ///
+    /// Such programs might not run on implementations that only have 4 KB of memory.
+    #[must_use]
+    pub fn stack_area_overlap_risk(&self) -> bool {
+        self.rom_start_address() as usize + self.rom_length > VIP_STACK_START
+    }
+
+    /// Start tracking which addresses are fetched as part of an instruction, for measuring how
+    /// much of a CHIP-8 program a test exercises. Both bytes of each fetched opcode are covered.
+    pub fn enable_coverage(&mut self) {
+        if self.coverage.is_none() {
+            self.coverage = Some(Box::new([false; 65536]));
+        }
+    }
+
+    /// The addresses that have been fetched as part of an instruction since
+    /// [`enable_coverage`](Chip8::enable_coverage) was called, or `None` if coverage tracking
+    /// isn't enabled.
+    #[must_use]
+    pub fn coverage_map(&self) -> Option<&[bool; 65536]> {
+        self.coverage.as_deref()
+    }
+
+    /// The fraction, from 0 to 1, of the loaded CHIP-8 program's bytes that have been fetched as
+    /// part of an instruction, or `None` if coverage tracking isn't enabled.
+    ///
+    /// Data in the program is never fetched, so a program with data never reaches 1.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn coverage_percentage(&self) -> Option<f64> {
+        let coverage = self.coverage.as_deref()?;
+        if self.rom_length == 0 {
+            return Some(0.0);
+        }
+        let start = self.rom_start_address() as usize;
+        let end = (start + self.rom_length).min(coverage.len());
+        let covered = coverage[start..end]
+            .iter()
+            .filter(|&&covered| covered)
+            .count();
+        Some(covered as f64 / self.rom_length as f64)
     }
 
     /// Set variable register's value.
@@ -98,98 +777,329 @@ impl Chip8 {
         self.v[usize::from(register)]
     }
 
+    /// A read-only view of the CHIP-8 memory.
+    ///
+    /// This replaces the formerly public `memory` field. Code that read `chip8.memory[addr]` can
+    /// use `chip8.memory_view().read_byte(addr)` or `chip8[addr]` instead, and code that wrote to
+    /// it can use `chip8[addr] = value`.
+    #[must_use]
+    pub fn memory_view(&self) -> MemoryView<'_> {
+        MemoryView(&self.memory)
+    }
+
+    /// The whole CHIP-8 memory, for frontends like memory editors that need all of it at once.
+    ///
+    /// This is a slice rather than a `&[u8; 65536]`, since memory is only 4 KB with
+    /// [`MemoryModel::Small`]. Its length is always the size of the memory model.
+    #[must_use]
+    pub fn memory_dump(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// The whole CHIP-8 memory, for writing.
+    ///
+    /// Like [`memory_dump`](Chip8::memory_dump), this is a slice as long as the memory model.
+    ///
+    /// Be careful: this makes it possible to overwrite the font and the running program, and
+    /// the interpreter's bookkeeping, like [`rom_length`](Chip8::rom_length), isn't updated.
+    #[must_use]
+    pub fn memory_dump_mut(&mut self) -> &mut [u8] {
+        &mut self.memory
+    }
+
+    /// The memory from `start` up to, but not including, `end`, or `None` if the range is
+    /// reversed or extends past the end of memory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::Chip8;
+    /// let mut chip8 = Chip8::default();
+    /// chip8.read_rom(&[0x12, 0x34]);
+    /// assert_eq!(chip8.memory_region_slice(0x200, 0x202), Some(&[0x12, 0x34][..]));
+    /// assert_eq!(chip8.memory_region_slice(0xFFFF, 0x10001), None);
+    /// ```
+    #[must_use]
+    pub fn memory_region_slice(&self, start: usize, end: usize) -> Option<&[u8]> {
+        self.memory.get(start..end)
+    }
+
+    /// Statistics about how the memory is used, for analyzing CHIP-8 programs.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn memory_statistics(&self) -> MemoryStats {
+        let mut counts = [0_usize; 256];
+        for &byte in self.memory.iter() {
+            counts[usize::from(byte)] += 1;
+        }
+        let total = self.memory.len() as f64;
+        let entropy = counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / total;
+                -p * p.log2()
+            })
+            .sum();
+        let font_address = self.font_address();
+        MemoryStats {
+            font_region: (font_address, font_address + FONT_LENGTH),
+            rom_region: (
+                usize::from(self.rom_start_address()),
+                self.effective_memory_end(),
+            ),
+            zero_bytes: counts[0],
+            total_nonzero: self.memory.len() - counts[0],
+            entropy,
+        }
+    }
+
+    /// A view of the variable registers with named accessors.
+    #[must_use]
+    pub fn registers(&self) -> RegisterView<'_> {
+        RegisterView(&self.v)
+    }
+
+    /// A mutable view of the variable registers.
+    pub fn registers_mut(&mut self) -> RegisterViewMut<'_> {
+        RegisterViewMut(&mut self.v)
+    }
+
     /// Fetch the next opcode from memory and increment the Program Counter.
     ///
     /// Note that this method does not guarantee that the Program Counter will point at the
     /// next opcode afterwards, as the opcode might have an immediate operand which is not
     /// fetched until decoding (in the case of an [`Instruction::SetIndexLong`]).
+    ///
+    /// The Program Counter wraps around from the end of memory to address `0x0000`, so an opcode
+    /// at `0xFFFF` is read from `0xFFFF` and `0x0000`.
     pub fn fetch(&mut self) -> u16 {
-        let opcode = (u16::from(self.memory[self.pc as usize]) << 8)
-            | u16::from(self.memory[self.pc.wrapping_add(1) as usize]);
+        let opcode = self.opcode_at(self.pc);
+        self.pc_high_watermark = Some(self.pc_high_watermark.map_or(self.pc, |pc| pc.max(self.pc)));
+        self.fetch_address = self.pc;
+        if let Some(coverage) = &mut self.coverage {
+            coverage[self.pc as usize] = true;
+            coverage[self.pc.wrapping_add(1) as usize] = true;
+        }
         self.pc = self.pc.wrapping_add(2);
         opcode
     }
 
+    /// Read the next opcode, like [`fetch`](Chip8::fetch) would, without changing the Program
+    /// Counter.
+    #[must_use]
+    pub fn peek(&self) -> u16 {
+        self.opcode_at(self.pc)
+    }
+
+    /// Read and decode the next instruction, like [`instruction_at`](Chip8::instruction_at)
+    /// with the Program Counter, for debuggers that show the next instruction without stepping.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`Chip8Error::Decode`] if the opcode doesn't map to an instruction.
+    #[must_use = "this Result must be checked for decoding errors"]
+    pub fn peek_instruction(&self) -> (u16, Result<Instruction, Chip8Error>) {
+        self.instruction_at(self.pc)
+    }
+
+    /// Read the opcode at an address in memory, without changing the Program Counter.
+    #[must_use]
+    pub fn opcode_at(&self, addr: u16) -> u16 {
+        (u16::from(self[addr]) << 8) | u16::from(self[addr.wrapping_add(1)])
+    }
+
+    /// Read and decode the instruction at an address in memory, without changing the Program
+    /// Counter.
+    ///
+    /// Returns the opcode along with the decoded instruction. An [`Instruction::SetIndexLong`]
+    /// is decoded into an [`Instruction::SetIndex`] with the address following the opcode.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`Chip8Error::Decode`] if the opcode doesn't map to an instruction.
+    #[must_use = "this Result must be checked for decoding errors"]
+    pub fn instruction_at(&self, addr: u16) -> (u16, Result<Instruction, Chip8Error>) {
+        let opcode = self.opcode_at(addr);
+        let instruction = match Instruction::try_from(opcode) {
+            Ok(Instruction::SetIndexLong) => {
+                Ok(Instruction::SetIndex(self.opcode_at(addr.wrapping_add(2))))
+            }
+            Ok(instruction) => Ok(instruction),
+            Err(_) => Err(Chip8Error::Decode(DecodeError::UnknownOpcode {
+                opcode,
+                pc: addr,
+            })),
+        };
+        (opcode, instruction)
+    }
+
+    /// Write a disassembly of the memory from `start` up to (but not including) `end`, one line
+    /// per instruction, like `0200: 00E0  CLS`.
+    ///
+    /// An [`Instruction::SetIndexLong`] takes up four bytes, which are all written. Opcodes that
+    /// don't map to an instruction are written as `???? (0xFFFF)`. The writer is only flushed at
+    /// the end, so it should be buffered.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if writing fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::Chip8;
+    /// let mut chip8 = Chip8::default();
+    /// chip8.read_rom(&[0x00, 0xE0, 0x12, 0x00]);
+    /// let mut output = Vec::new();
+    /// chip8.disassemble_to_writer(0x200, 0x204, &mut output).unwrap();
+    /// assert_eq!(output, b"0200: 00E0  CLS\n0202: 1200  JP\n");
+    /// ```
+    #[cfg(feature = "std")]
+    #[must_use = "this Result must be checked for write errors"]
+    pub fn disassemble_to_writer<W: std::io::Write>(
+        &self,
+        start: u16,
+        end: u16,
+        writer: &mut W,
+    ) -> std::io::Result<()> {
+        // Count in a u32, so a disassembly that runs to the end of memory doesn't wrap around
+        let mut addr = u32::from(start);
+        while addr < u32::from(end) {
+            #[allow(clippy::cast_possible_truncation)]
+            let address = addr as u16;
+            let opcode = self.opcode_at(address);
+            addr += match Instruction::try_from(opcode) {
+                Ok(Instruction::SetIndexLong) => {
+                    let operand = self.opcode_at(address.wrapping_add(2));
+                    writeln!(writer, "{address:04X}: {opcode:04X} {operand:04X}  LD")?;
+                    4
+                }
+                Ok(instruction) => {
+                    let mnemonic = disassembly::mnemonic(&instruction);
+                    writeln!(writer, "{address:04X}: {opcode:04X}  {mnemonic}")?;
+                    2
+                }
+                Err(_) => {
+                    writeln!(writer, "{address:04X}: {opcode:04X}  ???? ({opcode:#06X})")?;
+                    2
+                }
+            };
+        }
+        writer.flush()
+    }
+
     /// Decode a CHIP-8 opcode into an `[Instruction]`.
     ///
+    /// An [`Instruction::SetIndexLong`] is decoded into an [`Instruction::SetIndex`] by fetching
+    /// its immediate operand, which wraps around to the start of memory if the instruction is at
+    /// `0xFFFE`.
+    ///
+    /// If decoding fails, the Program Counter is left where it was, so decoding the same opcode
+    /// again gives the same result.
+    ///
     /// # Errors
     ///
-    /// Returns an `Err` with an error message if the opcode doesn't map to an instruction.
+    /// Returns a [`Chip8Error::Decode`] if the opcode doesn't map to an instruction. Its `pc` is
+    /// the address the most recent opcode was fetched from, or the start address if nothing has
+    /// been fetched yet.
     ///
     /// # Examples
     ///
     /// ```
-    /// # use deca::{Chip8, Instruction, Options};
+    /// # use deca::{Chip8, Chip8Error, DecodeError, Instruction};
     /// let mut chip8 = Chip8::default();
-    /// assert_eq!(chip8.decode(0x00E0), Ok(Instruction::Clear));
+    /// assert!(matches!(chip8.decode(0x00E0), Ok(Instruction::Clear)));
+    /// assert!(matches!(
+    ///     chip8.decode(0xFFFF),
+    ///     Err(Chip8Error::Decode(DecodeError::UnknownOpcode { opcode: 0xFFFF, pc: 0x0200 }))
+    /// ));
     /// ```
-    pub fn decode(&mut self, opcode: u16) -> Result<Instruction, String> {
-        let _foo = Instruction::try_from(opcode);
-        match _foo {
-            Ok(opcode) => match opcode {
-                Instruction::SetIndexLong => Ok(Instruction::SetIndex(self.fetch())),
-                instruction => Ok(instruction),
-            },
-            Err(e) => Err(format!("{e} at PC {}", self.pc)),
+    #[must_use = "this Result must be checked for decoding errors"]
+    pub fn decode(&mut self, opcode: u16) -> Result<Instruction, Chip8Error> {
+        match Instruction::try_from(opcode) {
+            Ok(Instruction::SetIndexLong) => Ok(Instruction::SetIndex(self.fetch())),
+            Ok(instruction) => Ok(instruction),
+            Err(_) => Err(Chip8Error::Decode(DecodeError::UnknownOpcode {
+                opcode,
+                pc: self.fetch_address,
+            })),
         }
-        //Ok(match Instruction::try_from(opcode)? {
-        //    Instruction::SetIndexLong => Instruction::SetIndex(self.fetch()),
-        //    instruction => instruction,
-        //})
     }
 
-    /// Execute a CHIP-8 `[Instruction]`.
+    /// Decode a raw opcode and execute it, without fetching it from memory first.
     ///
     /// # Errors
     ///
-    /// Returns an `Err` with an error message if the opcode caused a runtime CHIP-8 error.
+    /// Returns `Err` if the opcode can't be decoded, like [`decode`](Chip8::decode), or if
+    /// executing it fails, like [`execute`](Chip8::execute).
     ///
-    /// # Panics
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::Chip8;
+    /// let mut chip8 = Chip8::default();
+    /// chip8.execute_raw(0x6005).unwrap();
+    /// assert_eq!(chip8.v[0], 5);
+    /// ```
+    #[must_use = "this Result must be checked for CHIP-8 execution errors"]
+    pub fn execute_raw(&mut self, opcode: u16) -> Result<(), Chip8Error> {
+        let instruction = self.decode(opcode)?;
+        self.execute(instruction)
+    }
+
+    /// Execute a CHIP-8 `[Instruction]`.
+    ///
+    /// Instructions that access several bytes of memory from I onwards, like
+    /// [`Store`](Instruction::Store), [`Load`](Instruction::Load) and [`Bcd`](Instruction::Bcd),
+    /// wrap around from the end of memory to `0x0000`, which is `0xFFF` with
+    /// [4 KB of memory](MemoryModel::Small). I itself wraps around from `0xFFFF` to `0x0000`, when
+    /// `Store` and `Load` increment it because the `load_store` quirk is disabled.
+    ///
+    /// # Errors
     ///
-    /// Should only panic if executing an unimplemented instruction.
+    /// Returns an `Err` if the opcode caused a runtime CHIP-8 error, or if the instruction isn't
+    /// implemented by the interpreter or any of its [`extensions`](Chip8::extensions).
     // Allow unwrapping; should only be used when casting eg. a u4 into a larger number type like usize
     #[allow(clippy::too_many_lines, clippy::unwrap_used)]
-    pub fn execute(&mut self, instruction: Instruction) -> Result<(), String> {
+    #[must_use = "this Result must be checked for CHIP-8 execution errors"]
+    pub fn execute(&mut self, instruction: Instruction) -> Result<(), Chip8Error> {
         match instruction {
             #![allow(clippy::match_same_arms, clippy::cast_possible_truncation)]
-            Instruction::Exit(Some(n)) => {
-                return Err(format!("Interpreter exited with exit code {n}"))
-            }
-            Instruction::Exit(None) => return Err("Interpreter exited".to_string()),
+            Instruction::Exit(Some(n)) => return Err(Chip8Error::Exit(Some(u8::from(n)))),
+            Instruction::Exit(None) => return Err(Chip8Error::Exit(None)),
             Instruction::ScrollUp(n) => self.display.scroll_up(u8::from(n)),
             Instruction::ScrollDown(n) => self.display.scroll_down(u8::from(n)),
-            Instruction::Clear => self.display.clear(false),
+            Instruction::Clear => self.display.clear_active_plane(),
             Instruction::Return => {
                 if self.sp == 0 {
-                    return Err(String::from("Attempted pop from empty stack"));
+                    return Err(Chip8Error::StackUnderflow {
+                        stack_frames: self.stack_frames(),
+                    });
                 }
-                self.pc = self.stack[self.sp];
                 self.sp -= 1;
+                self.pc = self.stack[self.sp];
             }
+            // An unset load/store quirk behaves as if it's disabled, so the first toggle enables it
             Instruction::ToggleLoadStoreQuirk => {
                 self.options.quirks.load_store =
                     Some(!self.options.quirks.load_store.unwrap_or(false));
             }
+            // Unlike scrolling up and down, the SUPER-CHIP instructions for scrolling left and
+            // right don't have an operand, and always scroll 4 pixels
             Instruction::ScrollRight => self.display.scroll_right(4),
             Instruction::ScrollLeft => self.display.scroll_left(4),
-            Instruction::LoRes => self
-                .display
-                .lores(self.options.quirks.res_clear == Some(true)),
-            Instruction::HiRes => self
-                .display
-                .hires(self.options.quirks.res_clear == Some(true)),
-            Instruction::CallMachineCode(_) => {
-                return Err(String::from("Machine code is not supported"))
+            Instruction::LoRes => {
+                self.display
+                    .lores(self.options.quirks.res_clear == Some(true));
             }
-            Instruction::Jump(nnn) => self.pc = u16::from(nnn),
-            Instruction::Call(nnn) => {
-                self.sp += 1;
-                if self.sp >= self.stack.len() {
-                    return Err(String::from("Stack limit exceeded"));
-                }
-                self.stack[self.sp] = self.pc;
-                self.pc = u16::from(nnn);
+            Instruction::HiRes => {
+                self.display
+                    .hires(self.options.quirks.res_clear == Some(true));
             }
+            Instruction::CallMachineCode(_) => return Err(Chip8Error::MachineCodeNotSupported),
+            Instruction::Jump(nnn) => self.pc = u16::from(nnn),
+            Instruction::Call(nnn) => self.interrupt(u16::from(nnn))?,
             Instruction::SkipIfEqual(Register(x), Byte::Immediate(kk)) => {
                 if self.v[usize::try_from(x).unwrap()] == kk {
                     self.skip();
@@ -225,58 +1135,50 @@ impl Chip8 {
                 self.v[usize::try_from(x).unwrap()] ^= self.v[usize::try_from(y).unwrap()];
             }
             Instruction::Add(Register(x), Byte::Register(Register(y))) => {
-                self.v[0xF] = if (u16::from(self.v[usize::try_from(x).unwrap()])
-                    + u16::from(self.v[usize::try_from(y).unwrap()]))
-                    > 0xFF
-                {
+                let (x, y) = (usize::try_from(x).unwrap(), usize::try_from(y).unwrap());
+                let flag = if (u16::from(self.v[x]) + u16::from(self.v[y])) > 0xFF {
                     1
                 } else {
                     0
                 };
-                // FIXME
-                self.v[usize::try_from(x).unwrap()] = self.v[usize::try_from(x).unwrap()]
-                    .wrapping_add(self.v[usize::try_from(y).unwrap()]);
+                self.set_with_flag(x, y, flag, u8::wrapping_add);
             }
             Instruction::Sub(Register(x), Register(y)) => {
-                self.v[0xF] = match self.v[usize::from(u8::from(x))]
-                    .overflowing_sub(self.v[usize::try_from(y).unwrap()])
-                {
+                let (x, y) = (usize::try_from(x).unwrap(), usize::try_from(y).unwrap());
+                let flag = match self.v[x].overflowing_sub(self.v[y]) {
                     (_, true) => 0,
                     _ => 1,
                 };
-                self.v[usize::try_from(x).unwrap()] = self.v[usize::try_from(x).unwrap()]
-                    .wrapping_sub(self.v[usize::try_from(y).unwrap()]);
+                self.set_with_flag(x, y, flag, u8::wrapping_sub);
             }
+            // The operand is read before VF is written, so if VY is VF, the original VF is
+            // shifted, and if VX is VF, it ends up with the flag unless the VF conflict quirk
+            // writes VF first
             Instruction::ShiftLeft(Register(x), Register(y)) => {
-                dbg!(self.options.quirks.shift);
                 let operand: u8 = if self.options.quirks.shift == Some(true) {
                     self.v[usize::try_from(x).unwrap()]
                 } else {
                     self.v[usize::try_from(y).unwrap()]
                 };
-                self.v[0xF] = operand & 1;
-                self.v[usize::try_from(x).unwrap()] = operand >> 1;
+                let (x, y) = (usize::try_from(x).unwrap(), usize::try_from(y).unwrap());
+                self.set_with_flag(x, y, operand & 1, |_, _| operand >> 1);
             }
             Instruction::SubReverse(Register(x), Register(y)) => {
-                self.v[0xF] = match self.v[usize::from(u8::from(y))]
-                    .overflowing_sub(self.v[usize::try_from(x).unwrap()])
-                {
+                let (x, y) = (usize::try_from(x).unwrap(), usize::try_from(y).unwrap());
+                let flag = match self.v[y].overflowing_sub(self.v[x]) {
                     (_, true) => 0,
                     _ => 1,
                 };
-                self.v[usize::try_from(x).unwrap()] = self.v[usize::from(u8::from(y))]
-                    .wrapping_sub(self.v[usize::try_from(x).unwrap()]);
+                self.set_with_flag(x, y, flag, |vx, vy| vy.wrapping_sub(vx));
             }
             Instruction::ShiftRight(Register(x), Register(y)) => {
-                dbg!(self.options.quirks.shift);
                 let operand: u8 = if self.options.quirks.shift == Some(true) {
                     self.v[usize::try_from(x).unwrap()]
                 } else {
                     self.v[usize::try_from(y).unwrap()]
                 };
-
-                self.v[0xF] = (operand & 0x80) >> 7;
-                self.v[usize::try_from(x).unwrap()] = operand << 1;
+                let (x, y) = (usize::try_from(x).unwrap(), usize::try_from(y).unwrap());
+                self.set_with_flag(x, y, (operand & 0x80) >> 7, |_, _| operand << 1);
             }
             Instruction::SkipIfNotEqual(Register(x), Byte::Register(Register(y))) => {
                 if self.v[usize::try_from(x).unwrap()] != self.v[usize::try_from(y).unwrap()] {
@@ -290,10 +1192,12 @@ impl Chip8 {
                 } else {
                     self.v[0]
                 });
-                self.pc = jump_register + nnn;
+                // At most 0xFF + 0xFFF = 0x10FE, which is past the end of 4 KB of memory, but
+                // wraps around like the rest of the address space if it ever overflows
+                self.pc = jump_register.wrapping_add(nnn);
             }
             Instruction::Random(Register(x), kk) => {
-                self.v[usize::try_from(x).unwrap()] = fastrand::u8(..) & kk;
+                self.v[usize::try_from(x).unwrap()] = self.rng.u8(..) & kk;
             }
             Instruction::Draw(Register(x), Register(y), n) => {
                 let mut width: u8 = 8;
@@ -314,68 +1218,75 @@ impl Chip8 {
                     }
                 }
 
+                let (x, y) = (
+                    self.v[usize::try_from(x).unwrap()],
+                    self.v[usize::try_from(y).unwrap()],
+                );
                 let active_plane = self.display.active_plane;
+                // The active plane is restored when the guard is dropped, however drawing ends
+                let mut display = PlaneGuard::new(&mut self.display);
+                // A collision in any of the active planes sets VF. Each plane only toggles and
+                // checks its own bit of each pixel, so drawing the planes one after the other
+                // gives the same result as drawing them all at once.
+                let mut collision = 0;
                 for color in 1..=2 {
                     if active_plane & color != 0 {
-                        let mut sprite = Vec::<Vec<u8>>::new();
-                        for _y in 0..height {
-                            let mut row = Vec::<u8>::new();
-                            let mut byte = self.memory[address as usize];
-                            for x in 0..width {
-                                if x == 8 {
-                                    address = address.wrapping_add(1);
-                                    byte = self.memory[address as usize];
-                                }
-                                row.push((byte << (x % 8)) >> 7);
-                            }
-                            sprite.push(row);
+                        // At most 16 rows of 2 bytes each
+                        let mut sprite = [0; 32];
+                        let length = usize::from(height) * usize::from(width / 8);
+                        for byte in &mut sprite[..length] {
+                            *byte = self.memory[self.memory.wrap_address(address)];
                             address = address.wrapping_add(1);
                         }
 
-                        self.display.active_plane = color;
-                        self.v[0xF] = self.display.draw(
-                            sprite,
-                            self.v[usize::try_from(x).unwrap()],
-                            self.v[usize::try_from(y).unwrap()],
+                        display.active_plane = color;
+                        collision |= display.draw_raw_sprite(
+                            x,
+                            y,
+                            &sprite[..length],
+                            width,
+                            DrawFlags::default(),
                         );
                     }
                 }
-                self.display.active_plane = active_plane;
+                drop(display);
+                self.v[0xF] = collision;
+                self.draw_calls += 1;
             }
+            // Only the lowest nibble of VX selects a key, like on the COSMAC VIP
             Instruction::SkipKey(Register(x)) => {
-                if self.keyboard[usize::from(self.v[usize::try_from(x).unwrap()])] {
+                self.keyboard_read = true;
+                if self.keyboard[usize::from(self.v[usize::try_from(x).unwrap()] & 0xF)] {
                     self.skip();
                 }
             }
             Instruction::SkipNotKey(Register(x)) => {
-                if !self.keyboard[usize::from(self.v[usize::try_from(x).unwrap()])] {
+                self.keyboard_read = true;
+                if !self.keyboard[usize::from(self.v[usize::try_from(x).unwrap()] & 0xF)] {
                     self.skip();
                 }
             }
-            Instruction::SoundStuff => todo!(),
+            // XO-CHIP audio isn't implemented by the interpreter itself, so it's left to the
+            // extensions, and is a Chip8Error::UnimplementedInstruction if none of them handle it
+            Instruction::SoundStuff => return self.execute_extensions(instruction),
             Instruction::LoadDelay(Register(x)) => self.v[usize::try_from(x).unwrap()] = self.delay,
             Instruction::BlockKey(Register(x)) => {
+                self.keyboard_read = true;
                 self.pc = self.pc.wrapping_sub(2);
-                for key in 0..self.keyboard.len() {
-                    if self.keyboard[key] {
-                        self.v[usize::try_from(x).unwrap()] = key as u8;
-                        self.skip();
-                        self.keyboard[key] = false;
-                        break;
-                    }
-                }
-            }
-            Instruction::SelectPlane(n) => {
-                let n: u8 = n.into();
-                if n > 3 {
-                    return Err(format!(
-                        "XO-CHIP currently only supports 3 planes, attempted to select plane {}",
-                        n
-                    ));
+                let mut pressed = (0..self.keyboard.len()).filter(|&key| self.keyboard[key]);
+                let key = if self.block_key_highest == Some(true) {
+                    pressed.next_back()
+                } else {
+                    pressed.next()
+                };
+                if let Some(key) = key {
+                    self.v[usize::try_from(x).unwrap()] = key as u8;
+                    self.skip();
+                    self.keyboard[key] = false;
                 }
-                self.display.plane(n);
             }
-            Instruction::SoundStuffTwo => todo!(),
+            Instruction::SelectPlane(n) => self.display.plane(n.into())?,
+            Instruction::SoundStuffTwo => return self.execute_extensions(instruction),
             Instruction::SetDelay(Register(x)) => self.delay = self.v[usize::try_from(x).unwrap()],
             Instruction::SetSound(Register(x)) => self.sound = self.v[usize::try_from(x).unwrap()],
             Instruction::AddRegisterToIndex(Register(x)) => {
@@ -384,22 +1295,25 @@ impl Chip8 {
                     .wrapping_add(u16::from(self.v[usize::try_from(x).unwrap()]));
             }
             Instruction::FontCharacter(Register(x)) => {
-                self.i = 0x50 + u16::from(self.v[usize::try_from(x).unwrap()] * 5);
+                let character = self.font_character(self.v[usize::try_from(x).unwrap()])?;
+                self.i = self.font_address() + u16::from(character * 5);
             }
             Instruction::BigFontCharacter(Register(x)) => {
-                self.i = 0xA0 + u16::from(self.v[usize::try_from(x).unwrap()] * 10);
+                let character = self.font_character(self.v[usize::try_from(x).unwrap()])?;
+                self.i = self.font_address() + 80 + u16::from(character * 10);
             }
             Instruction::Bcd(Register(x)) => {
                 let vx: u8 = self.v[usize::try_from(x).unwrap()];
-                self.memory[self.i as usize] = vx / 100;
-                self.memory[self.i as usize + 1] = (vx / 10) % 10;
-                self.memory[self.i as usize + 2] = vx % 10;
+                let i = self.i;
+                self[i] = vx / 100;
+                self[i.wrapping_add(1)] = (vx / 10) % 10;
+                self[i.wrapping_add(2)] = vx % 10;
             }
             Instruction::Store(Register(x)) => {
                 let mut i = self.i;
                 let x = usize::try_from(x).unwrap();
                 for n in 0..=x {
-                    self.memory[i as usize] = self.v[n];
+                    self[i] = self.v[n];
                     i = i.wrapping_add(1);
                 }
                 if self.options.quirks.load_store != Some(true) {
@@ -411,7 +1325,7 @@ impl Chip8 {
                 let x = usize::try_from(x).unwrap();
 
                 for n in 0..=x {
-                    self.v[n] = self.memory[i as usize];
+                    self.v[n] = self[i];
                     i = i.wrapping_add(1);
                 }
                 if self.options.quirks.load_store != Some(true) {
@@ -427,7 +1341,7 @@ impl Chip8 {
                 } else {
                     Either::Right((y..=x).rev())
                 } {
-                    self.memory[i as usize] = self.v[n];
+                    self[i] = self.v[n];
                     i = i.wrapping_add(1);
                 }
             }
@@ -440,7 +1354,7 @@ impl Chip8 {
                 } else {
                     Either::Right((y..=x).rev())
                 } {
-                    self.v[n] = self.memory[i as usize];
+                    self.v[n] = self[i];
                     i = i.wrapping_add(1);
                 }
             }
@@ -457,43 +1371,817 @@ impl Chip8 {
                 }
             }
             Instruction::SetIndexLong => self.i = self.fetch(),
-            _ => panic!("Unknown instruction {:?}", instruction),
         }
         Ok(())
     }
 
+    /// The font character to point I at for a value of VX, according to the font character
+    /// quirk.
+    fn font_character(&self, vx: u8) -> Result<u8, Chip8Error> {
+        if vx <= 0xF || self.font_character_mask_quirk != Some(false) {
+            Ok(vx & 0xF)
+        } else {
+            Err(Chip8Error::InvalidFontCharacter(vx))
+        }
+    }
+
+    /// Write the result of an arithmetic instruction to VX and its flag to VF.
+    ///
+    /// By default, the result is written first, so the flag ends up in VF even if VX is VF. If the
+    /// VF conflict quirk is `false`, VF is written first, so the result is computed from the
+    /// updated VF if either operand is VF.
+    fn set_with_flag(&mut self, x: usize, y: usize, flag: u8, op: impl Fn(u8, u8) -> u8) {
+        if self.vf_conflict_quirk != Some(false) {
+            self.v[x] = op(self.v[x], self.v[y]);
+            self.v[0xF] = flag;
+        } else {
+            self.v[0xF] = flag;
+            self.v[x] = op(self.v[x], self.v[y]);
+        }
+    }
+
+    /// The current call stack, most recent first, if stack traces are enabled.
+    fn stack_frames(&self) -> Vec<u16> {
+        if !self.stack_trace_in_errors {
+            return Vec::new();
+        }
+        std::iter::once(self.pc)
+            .chain(self.stack[..self.sp].iter().rev().copied())
+            .collect()
+    }
+
+    /// Pass an instruction on to each extension in turn, until one of them handles it.
+    fn execute_extensions(&mut self, instruction: Instruction) -> Result<(), Chip8Error> {
+        let mut extensions = std::mem::take(&mut self.extensions);
+        let result = extensions
+            .iter_mut()
+            .find_map(|extension| extension.execute(self, instruction));
+        // An extension might have added more extensions while executing
+        extensions.append(&mut self.extensions);
+        self.extensions = extensions;
+        result.unwrap_or(Err(Chip8Error::UnimplementedInstruction(instruction)))
+    }
+
     /// Run the CHIP-8 CPU for the given number of ticks.
     ///
+    /// Fewer instructions than `tickrate` might be executed, if the `vblank` quirk is enabled and
+    /// the program draws a sprite. The returned [`RunResult`] reports how many were executed.
+    ///
+    /// The timers are ticked first, and then any [`interrupt_pending`](Chip8::interrupt_pending)
+    /// is handled, before the first instruction of the frame is executed. With a `tickrate` of
+    /// 0, nothing happens at all, so frontends can call `run(0)` while paused.
+    ///
     /// # Errors
     ///
-    /// Returns `Err` if a runtime CHIP-8 error occurs during execution.
-    pub fn run(&mut self, tickrate: u16) -> Result<(), String> {
-        if self.options.quirks.delay_wrap != Some(true) && self.delay > 0 {
-            self.delay = self.delay.wrapping_sub(1);
+    /// Returns `Err` if a runtime CHIP-8 error occurs during execution, if a pending interrupt
+    /// overflows the call stack, or if
+    /// [`loop_detection`](Chip8::loop_detection) is enabled and the program has been stuck in an
+    /// infinite loop for [`loop_detection_threshold`](Chip8::loop_detection_threshold) frames.
+    #[must_use = "this Result must be checked for CHIP-8 execution errors"]
+    pub fn run(&mut self, tickrate: u16) -> Result<RunResult, Chip8Error> {
+        if tickrate == 0 {
+            return Ok(RunResult::default());
         }
-        if self.sound > 0 {
-            self.sound -= 1;
+        self.display.frame_number += 1;
+        self.tick_timers();
+        if let Some(vector) = self.interrupt_pending.take() {
+            self.interrupt(vector)?;
         }
+        let mut result = RunResult::default();
         for _ in 0..tickrate {
-            let _addr = self.pc;
-            let opcode = self.fetch();
-            //dbg!(format!("{:02x}: {:04x}", _addr, opcode));
-            let instruction = self.decode(opcode)?;
-            self.execute(instruction)?;
-            if self.options.quirks.vblank == Some(true) && (0xD000..=0xDFFF).contains(&opcode) {
+            let instruction = self.step()?;
+            result.instructions_executed += 1;
+            if self.options.quirks.vblank == Some(true)
+                && matches!(instruction, Instruction::Draw(..))
+            {
+                self.vblank_count += 1;
+                result.stop_reason = StopReason::VBlank;
                 break;
             }
         }
+        if self.loop_detection {
+            self.detect_loop()?;
+        }
+        Ok(result)
+    }
+
+    /// Execute instructions until the display changes, or until `max_ticks` instructions have
+    /// been executed, for frontends that render after each change.
+    ///
+    /// The display's [`dirty`](Display::dirty) flag is unset first. Unlike [`run`](Chip8::run),
+    /// this doesn't tick the timers or count as a frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if a runtime CHIP-8 error occurs during execution.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::{Chip8, StopReason};
+    /// let mut chip8 = Chip8::default();
+    /// // 0x200: i := 0x050; 0x202: sprite v0 v0 1
+    /// chip8.read_rom(&[0xA0, 0x50, 0xD0, 0x01]);
+    /// let result = chip8.run_until_display_draw(10).unwrap();
+    /// assert_eq!(result.instructions_executed, 2);
+    /// assert_eq!(result.stop_reason, StopReason::DisplayDrawn);
+    /// ```
+    #[must_use = "this Result must be checked for CHIP-8 execution errors"]
+    pub fn run_until_display_draw(&mut self, max_ticks: u16) -> Result<RunResult, Chip8Error> {
+        self.display.dirty = false;
+        let mut result = RunResult::default();
+        for _ in 0..max_ticks {
+            self.step()?;
+            result.instructions_executed += 1;
+            if self.display.dirty {
+                result.stop_reason = StopReason::DisplayDrawn;
+                break;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Interrupt the CHIP-8 program, by calling a subroutine at the address `vector` like
+    /// [`Instruction::Call`] does. The subroutine returns to where the program was interrupted.
+    ///
+    /// This is meant for extensions that simulate hardware interrupts, like timers or DMA
+    /// completion. To interrupt the program at the start of the next frame instead, set
+    /// [`interrupt_pending`](Chip8::interrupt_pending).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Chip8Error::StackOverflow`] if the call stack is full.
+    #[must_use = "this Result must be checked for stack overflows"]
+    pub fn interrupt(&mut self, vector: u16) -> Result<(), Chip8Error> {
+        if self.sp >= self.stack.len() {
+            return Err(Chip8Error::StackOverflow {
+                stack_frames: self.stack_frames(),
+            });
+        }
+        self.stack[self.sp] = self.pc;
+        self.sp += 1;
+        self.pc = vector;
         Ok(())
     }
 
-    fn skip(&mut self) {
+    /// Check whether the program ended this frame in the same state as one of the previous frames.
+    ///
+    /// Programs that are waiting for a key press are never considered stuck, and programs waiting
+    /// for the delay timer aren't either, since the timer keeps changing.
+    fn detect_loop(&mut self) -> Result<(), Chip8Error> {
+        if std::mem::take(&mut self.keyboard_read) {
+            self.loop_history.clear();
+            self.loop_repeats = 0;
+            return Ok(());
+        }
+        let state = LoopState {
+            pc: self.pc,
+            i: self.i,
+            sp: self.sp,
+            v: self.v,
+            delay: self.delay,
+            sound: self.sound,
+        };
+        if self.loop_history.contains(&state) {
+            self.loop_repeats += 1;
+            if self.loop_repeats >= self.loop_detection_threshold {
+                return Err(Chip8Error::InfiniteLoopDetected { pc: self.pc });
+            }
+        } else {
+            self.loop_repeats = 0;
+            if self.loop_history.len() == LOOP_HISTORY_LENGTH {
+                self.loop_history.pop_front();
+            }
+            self.loop_history.push_back(state);
+        }
+        Ok(())
+    }
+
+    /// Fetch, decode and execute a single instruction, and return the instruction that was
+    /// executed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if a runtime CHIP-8 error occurs, or if the
+    /// [`execution_limit`](Chip8::execution_limit) has been reached.
+    #[must_use = "this Result must be checked for CHIP-8 execution errors"]
+    pub fn step(&mut self) -> Result<Instruction, Chip8Error> {
+        if let Some(limit) = self.execution_limit {
+            if self.cycles >= limit {
+                return Err(Chip8Error::ExecutionLimitExceeded(limit));
+            }
+        }
+        if self.reverse_history_len > 0 {
+            if self.reverse_history.len() == self.reverse_history_len {
+                // Reuse the oldest snapshot's buffers instead of allocating new ones
+                if let Some(mut snapshot) = self.reverse_history.pop_front() {
+                    self.snapshot_into(&mut snapshot);
+                    self.reverse_history.push_back(snapshot);
+                }
+            } else {
+                self.reverse_history.push_back(self.snapshot());
+            }
+        }
+        #[cfg(feature = "log")]
+        let addr = self.pc;
         let opcode = self.fetch();
-        if let Ok(instruction) = self.decode(opcode) {
-            if instruction == Instruction::SetIndexLong {
-                let _ = self.fetch();
+        let instruction = self.decode(opcode)?;
+        #[cfg(feature = "log")]
+        log::trace!("{addr:#06X}: {opcode:04X} {instruction:?}");
+        self.cycles += 1;
+        self.execute(instruction)?;
+        Ok(instruction)
+    }
+
+    /// Start keeping a snapshot before each instruction that's executed by [`step`](Chip8::step),
+    /// for up to `history_len` instructions, so they can be undone with
+    /// [`step_back`](Chip8::step_back). A `history_len` of 0 disables reverse stepping.
+    ///
+    /// Each snapshot includes all of memory, so a long history uses a lot of memory. Once the
+    /// history is full, the oldest snapshot's buffers are reused for each new snapshot.
+    pub fn enable_reverse_step(&mut self, history_len: usize) {
+        self.reverse_history_len = history_len;
+        while self.reverse_history.len() > history_len {
+            self.reverse_history.pop_front();
+        }
+    }
+
+    /// Undo the most recently executed instruction, by restoring the interpreter to the state it
+    /// was in before it. Returns `None` if there is no more history to restore.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::Chip8;
+    /// let mut chip8 = Chip8::default();
+    /// chip8.enable_reverse_step(16);
+    /// // v0 := 0x42
+    /// chip8.read_rom(&[0x60, 0x42]);
+    /// chip8.step().unwrap();
+    /// assert_eq!(chip8.step_back(), Some(()));
+    /// assert_eq!((chip8.pc, chip8.v[0]), (0x200, 0));
+    /// assert_eq!(chip8.step_back(), None);
+    /// ```
+    pub fn step_back(&mut self) -> Option<()> {
+        let snapshot = self.reverse_history.pop_back()?;
+        self.restore(&snapshot);
+        Some(())
+    }
+
+    /// Decrement the delay and sound timers, if they're non-zero. This should happen at 60 Hz.
+    ///
+    /// The delay timer normally stops at zero, but if the `delay_wrap` quirk is `true`, it's always
+    /// decremented, and wraps around from zero to 255.
+    pub fn tick_timers(&mut self) {
+        self.tick_timers_with_callback(|_| {});
+    }
+
+    /// Decrement the delay and sound timers like [`tick_timers`](Chip8::tick_timers), and call
+    /// the callback with any [`TimerEvent`]s that happened since the timers were last ticked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::{Chip8, TimerEvent};
+    /// let mut chip8 = Chip8::default();
+    /// chip8.sound = 1;
+    /// let mut events = Vec::new();
+    /// chip8.tick_timers_with_callback(|event| events.push(event));
+    /// assert_eq!(events, [TimerEvent::SoundStarted, TimerEvent::SoundStopped]);
+    /// ```
+    pub fn tick_timers_with_callback<F: FnMut(TimerEvent)>(&mut self, mut callback: F) {
+        if self.ticked_sound == 0 && self.sound > 0 {
+            callback(TimerEvent::SoundStarted);
+        }
+        self.prev_sound = self.sound;
+        let prev_delay = self.delay;
+        if self.options.quirks.delay_wrap == Some(true) {
+            self.delay = self.delay.wrapping_sub(1);
+        } else {
+            self.delay = self.delay.saturating_sub(1);
+        }
+        self.sound = self.sound.saturating_sub(1);
+        if self.prev_sound > 0 && self.sound == 0 {
+            callback(TimerEvent::SoundStopped);
+        }
+        if prev_delay > 0 && self.delay == 0 {
+            callback(TimerEvent::DelayReachedZero);
+        }
+        if self.delay != prev_delay || self.sound != self.prev_sound {
+            if let Some(timer_callback) = &mut self.timer_callback {
+                timer_callback(self.delay, self.sound);
+            }
+        }
+        self.ticked_sound = self.sound;
+    }
+
+    /// Set a callback that's called with the new values of the delay and sound timers whenever
+    /// the timers are ticked and at least one of them is decremented, for frontends that
+    /// visualize the timers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::Chip8;
+    /// # use std::sync::{Arc, Mutex};
+    /// let mut chip8 = Chip8::default();
+    /// let ticks = Arc::new(Mutex::new(Vec::new()));
+    /// let callback_ticks = Arc::clone(&ticks);
+    /// chip8.set_timer_callback(move |delay, sound| callback_ticks.lock().unwrap().push((delay, sound)));
+    /// chip8.delay = 2;
+    /// chip8.sound = 1;
+    /// for _ in 0..3 {
+    ///     chip8.tick_timers();
+    /// }
+    /// assert_eq!(*ticks.lock().unwrap(), [(1, 0), (0, 0)]);
+    /// ```
+    pub fn set_timer_callback<F: FnMut(u8, u8) + Send + 'static>(&mut self, f: F) {
+        self.timer_callback = Some(Box::new(f));
+    }
+
+    /// The current value of the delay timer.
+    #[must_use]
+    pub fn delay_timer(&self) -> u8 {
+        self.delay
+    }
+
+    /// Set the delay timer, like the program does with `FX15`.
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.delay = value;
+    }
+
+    /// The current value of the sound timer.
+    #[must_use]
+    pub fn sound_timer(&self) -> u8 {
+        self.sound
+    }
+
+    /// Set the sound timer, like the program does with `FX18`. A non-zero value starts the sound,
+    /// which is reported as [`TimerEvent::SoundStarted`] the next time the timers are ticked.
+    pub fn set_sound_timer(&mut self, value: u8) {
+        self.sound = value;
+    }
+
+    /// Whether sound should currently be playing.
+    #[must_use]
+    pub fn sound_active(&self) -> bool {
+        self.sound > 0
+    }
+
+    /// Whether sound started or stopped playing since the timers were last ticked.
+    ///
+    /// This lets a frontend start and stop its audio output without keeping track of the
+    /// previous sound timer value itself.
+    #[must_use]
+    pub fn sound_edge(&self) -> SoundEdge {
+        match (self.prev_sound > 0, self.sound_active()) {
+            (false, true) => SoundEdge::Started,
+            (true, false) => SoundEdge::Stopped,
+            _ => SoundEdge::Unchanged,
+        }
+    }
+
+    /// A checksum of the display, for comparing it against known display states in tests.
+    ///
+    /// This is the 32-bit FNV-1a hash of the pixels within the current resolution, row by row,
+    /// with each pixel as one byte whose bits denote the planes it's set in. It will stay the
+    /// same across versions of this crate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::Chip8;
+    /// let chip8 = Chip8::default();
+    /// assert_eq!(chip8.screen_checksum(), 0xD206_3DC5);
+    /// ```
+    #[must_use]
+    pub fn screen_checksum(&self) -> u32 {
+        const FNV_OFFSET_BASIS: u32 = 0x811C_9DC5;
+        const FNV_PRIME: u32 = 0x0100_0193;
+        self.display
+            .rows()
+            .flatten()
+            .fold(FNV_OFFSET_BASIS, |hash, &pixel| {
+                (hash ^ u32::from(pixel)).wrapping_mul(FNV_PRIME)
+            })
+    }
+
+    /// A multi-line description of the CPU state, with all the registers and the contents of the
+    /// call stack, for detailed crash reports.
+    #[must_use]
+    pub fn cpu_state_string(&self) -> String {
+        let mut state = format!(
+            "PC: {:#06X}  I: {:#06X}  SP: {}\n",
+            self.pc, self.i, self.sp
+        );
+        for (row, registers) in self.v.chunks(8).enumerate() {
+            let registers: Vec<String> = registers
+                .iter()
+                .enumerate()
+                .map(|(n, v)| format!("V{:X}: {v:02X}", row * 8 + n))
+                .collect();
+            state.push_str(&registers.join("  "));
+            state.push('\n');
+        }
+        state.push_str(&format!("DLY: {}  SND: {}\n", self.delay, self.sound));
+        state.push_str("Stack:");
+        for address in &self.stack[..self.sp] {
+            state.push_str(&format!(" {address:#06X}"));
+        }
+        state.push('\n');
+        state
+    }
+
+    /// A one-line dump of all the registers, like
+    /// `V0=00 V1=FF V2=12 ... VF=01 I=0300 PC=0204 SP=2 DLY=48 SND=0`, for assertion messages and
+    /// logs. This is also what the [`Display`](std::fmt::Display) implementation writes.
+    #[must_use]
+    pub fn dump_registers(&self) -> String {
+        let mut dump: Vec<String> = self
+            .v
+            .iter()
+            .enumerate()
+            .map(|(n, v)| format!("V{n:X}={v:02X}"))
+            .collect();
+        dump.push(format!(
+            "I={:04X} PC={:04X} SP={} DLY={} SND={}",
+            self.i, self.pc, self.sp, self.delay, self.sound
+        ));
+        dump.join(" ")
+    }
+
+    /// A one-line dump of the return addresses on the call stack, oldest first and numbered from
+    /// 1, like `STACK[1]=020A STACK[2]=0350`. It's empty if the stack is.
+    #[must_use]
+    pub fn dump_stack(&self) -> String {
+        let dump: Vec<String> = self.stack[..self.sp]
+            .iter()
+            .enumerate()
+            .map(|(n, address)| format!("STACK[{}]={address:04X}", n + 1))
+            .collect();
+        dump.join(" ")
+    }
+
+    /// Encode the display as a PNG image, with each pixel scaled up to `scale` by `scale` pixels.
+    ///
+    /// The colors are looked up in `palette` like with [`Display::to_rgba_buffer`]. This doesn't
+    /// touch the file system, so it can be used for web and WASM frontends.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `scale` is 0.
+    #[cfg(feature = "image")]
+    #[must_use]
+    pub fn display_to_png_bytes(&self, palette: &[u32; 4], scale: u8) -> Vec<u8> {
+        use image::ImageEncoder;
+
+        assert!(scale > 0, "the display can't be scaled by 0");
+        let scale = usize::from(scale);
+        let (width, height) = (
+            usize::from(self.display.width),
+            usize::from(self.display.height),
+        );
+        let rgba = self.display.to_rgba_buffer(palette);
+        let mut scaled = Vec::with_capacity(rgba.len() * scale * scale);
+        for row in rgba.chunks(width * 4) {
+            let mut scaled_row = Vec::with_capacity(row.len() * scale);
+            for pixel in row.chunks(4) {
+                for _ in 0..scale {
+                    scaled_row.extend_from_slice(pixel);
+                }
+            }
+            for _ in 0..scale {
+                scaled.extend_from_slice(&scaled_row);
             }
         }
+        let mut png = Vec::new();
+        #[allow(clippy::cast_possible_truncation)]
+        image::codecs::png::PngEncoder::new(&mut png)
+            .write_image(
+                &scaled,
+                (width * scale) as u32,
+                (height * scale) as u32,
+                image::ColorType::Rgba8,
+            )
+            .expect("the buffer matches the image dimensions");
+        png
+    }
+
+    /// Save the display as a PNG image file, like [`display_to_png_bytes`](Chip8::display_to_png_bytes).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the file can't be written.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `scale` is 0.
+    #[cfg(all(feature = "image", feature = "std"))]
+    #[must_use = "this Result must be checked for file errors"]
+    pub fn export_display_to_png(
+        &self,
+        path: &std::path::Path,
+        palette: &[u32; 4],
+        scale: u8,
+    ) -> std::io::Result<()> {
+        std::fs::write(path, self.display_to_png_bytes(palette, scale))
+    }
+
+    /// Compare this interpreter's state to another interpreter's.
+    ///
+    /// Memory is only compared from the start address to the end of the longer of the two loaded
+    /// CHIP-8 programs, and the display within this display's current resolution.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn state_diff(&self, other: &Chip8) -> Chip8Diff {
+        let start = self.rom_start_address() as usize;
+        let end = (start + self.rom_length.max(other.rom_length))
+            .min(self.memory.len())
+            .min(other.memory.len());
+        Chip8Diff {
+            pc_differs: self.pc != other.pc,
+            i_differs: self.i != other.i,
+            stack_differs: self.stack[..self.sp] != other.stack[..other.sp],
+            timers_differ: self.delay != other.delay || self.sound != other.sound,
+            registers: (0..16)
+                .filter(|&n| self.v[n] != other.v[n])
+                .map(|n| (n, self.v[n], other.v[n]))
+                .collect(),
+            memory_diffs: (start..end)
+                .filter(|&address| self.memory[address] != other.memory[address])
+                .map(|address| (address as u16, self.memory[address], other.memory[address]))
+                .collect(),
+            display_diffs: self.display.compare(&other.display),
+        }
+    }
+
+    /// Assert that this interpreter's state is the same as another interpreter's, as compared by
+    /// [`state_diff`](Chip8::state_diff).
+    ///
+    /// # Panics
+    ///
+    /// Panics with a list of the differences if the states differ.
+    #[track_caller]
+    pub fn assert_no_diff(&self, other: &Chip8) {
+        let diff = self.state_diff(other);
+        assert!(diff.is_empty(), "Interpreter states differ:\n{diff}");
+    }
+
+    /// Take a snapshot of the interpreter's current state, which can later be restored with
+    /// [`restore`](Chip8::restore).
+    ///
+    /// This allocates a new copy of the memory, the display and the stack every time. To take
+    /// snapshots often, like for save state slots or rewinding, create each snapshot once and
+    /// then update it with [`snapshot_into`](Chip8::snapshot_into).
+    #[must_use]
+    pub fn snapshot(&self) -> Chip8Snapshot {
+        Chip8Snapshot {
+            pc: self.pc,
+            sp: self.sp,
+            stack: self.stack.clone(),
+            memory: self.memory.clone(),
+            i: self.i,
+            v: self.v,
+            flags: self.flags,
+            delay: self.delay,
+            sound: self.sound,
+            display: Box::new(self.display.clone()),
+            options: self.options.clone(),
+            keyboard: self.keyboard,
+            prev_sound: self.prev_sound,
+            ticked_sound: self.ticked_sound,
+            rom_length: self.rom_length,
+            stack_trace_in_errors: self.stack_trace_in_errors,
+            cycles: self.cycles,
+            execution_limit: self.execution_limit,
+            vf_conflict_quirk: self.vf_conflict_quirk,
+            loop_detection: self.loop_detection,
+            loop_detection_threshold: self.loop_detection_threshold,
+            loop_history: self.loop_history.clone(),
+            loop_repeats: self.loop_repeats,
+            keyboard_read: self.keyboard_read,
+            hash_memory: self.hash_memory,
+            block_key_highest: self.block_key_highest,
+            font_character_mask_quirk: self.font_character_mask_quirk,
+            fetch_address: self.fetch_address,
+            interrupt_pending: self.interrupt_pending,
+        }
+    }
+
+    /// Overwrite an existing snapshot with the interpreter's current state, like
+    /// [`snapshot`](Chip8::snapshot), but reusing the snapshot's buffers instead of allocating
+    /// new ones. This is the way to save state every frame.
+    ///
+    /// The buffers can only be reused if the snapshot was taken with the same
+    /// [`MemoryModel`] and display size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use deca::Chip8;
+    /// let mut chip8 = Chip8::default();
+    /// // v0 += 1, jump 0x200
+    /// chip8.read_rom(&[0x70, 0x01, 0x12, 0x00]);
+    /// let mut save_state = chip8.snapshot();
+    /// for _ in 0..10 {
+    ///     chip8.run(2).unwrap();
+    ///     chip8.snapshot_into(&mut save_state);
+    /// }
+    /// chip8.run(2).unwrap();
+    /// chip8.restore(&save_state);
+    /// assert_eq!(chip8.v[0], 10);
+    /// ```
+    pub fn snapshot_into(&self, snapshot: &mut Chip8Snapshot) {
+        snapshot.pc = self.pc;
+        snapshot.sp = self.sp;
+        snapshot.stack.clone_from(&self.stack);
+        snapshot.memory.clone_from(&self.memory);
+        snapshot.i = self.i;
+        snapshot.v = self.v;
+        snapshot.flags = self.flags;
+        snapshot.delay = self.delay;
+        snapshot.sound = self.sound;
+        (*snapshot.display).clone_from(&self.display);
+        snapshot.options.clone_from(&self.options);
+        snapshot.keyboard = self.keyboard;
+        snapshot.prev_sound = self.prev_sound;
+        snapshot.ticked_sound = self.ticked_sound;
+        snapshot.rom_length = self.rom_length;
+        snapshot.stack_trace_in_errors = self.stack_trace_in_errors;
+        snapshot.cycles = self.cycles;
+        snapshot.execution_limit = self.execution_limit;
+        snapshot.vf_conflict_quirk = self.vf_conflict_quirk;
+        snapshot.loop_detection = self.loop_detection;
+        snapshot.loop_detection_threshold = self.loop_detection_threshold;
+        snapshot.loop_history.clone_from(&self.loop_history);
+        snapshot.loop_repeats = self.loop_repeats;
+        snapshot.keyboard_read = self.keyboard_read;
+        snapshot.hash_memory = self.hash_memory;
+        snapshot.block_key_highest = self.block_key_highest;
+        snapshot.font_character_mask_quirk = self.font_character_mask_quirk;
+        snapshot.fetch_address = self.fetch_address;
+        snapshot.interrupt_pending = self.interrupt_pending;
+    }
+
+    /// Restore the interpreter to the state it was in when the snapshot was taken. The
+    /// interpreter's [`extensions`](Chip8::extensions) are left as they are.
+    pub fn restore(&mut self, snapshot: &Chip8Snapshot) {
+        self.pc = snapshot.pc;
+        self.sp = snapshot.sp;
+        self.stack.clone_from(&snapshot.stack);
+        self.memory.clone_from(&snapshot.memory);
+        self.i = snapshot.i;
+        self.v = snapshot.v;
+        self.flags = snapshot.flags;
+        self.delay = snapshot.delay;
+        self.sound = snapshot.sound;
+        let counters = (
+            self.display.total_draw_calls,
+            self.display.total_collision_count,
+            self.display.total_clear_count,
+        );
+        self.display.clone_from(&snapshot.display);
+        (
+            self.display.total_draw_calls,
+            self.display.total_collision_count,
+            self.display.total_clear_count,
+        ) = counters;
+        self.options.clone_from(&snapshot.options);
+        self.keyboard = snapshot.keyboard;
+        self.prev_sound = snapshot.prev_sound;
+        self.ticked_sound = snapshot.ticked_sound;
+        self.rom_length = snapshot.rom_length;
+        self.stack_trace_in_errors = snapshot.stack_trace_in_errors;
+        self.cycles = snapshot.cycles;
+        self.execution_limit = snapshot.execution_limit;
+        self.vf_conflict_quirk = snapshot.vf_conflict_quirk;
+        self.loop_detection = snapshot.loop_detection;
+        self.loop_detection_threshold = snapshot.loop_detection_threshold;
+        self.loop_history.clone_from(&snapshot.loop_history);
+        self.loop_repeats = snapshot.loop_repeats;
+        self.keyboard_read = snapshot.keyboard_read;
+        self.hash_memory = snapshot.hash_memory;
+        self.block_key_highest = snapshot.block_key_highest;
+        self.font_character_mask_quirk = snapshot.font_character_mask_quirk;
+        self.fetch_address = snapshot.fetch_address;
+        self.interrupt_pending = snapshot.interrupt_pending;
+    }
+
+    /// Create a copy of this interpreter, for exploring several branches of execution
+    /// independently.
+    ///
+    /// `Chip8` doesn't implement [`Clone`], since its [`extensions`](Chip8::extensions) can't be
+    /// cloned. The copy has the same state as this interpreter, but no extensions or timer
+    /// callback, and a new [`rng`](Chip8::rng).
+    #[must_use]
+    pub fn clone_without_hooks(&self) -> Chip8 {
+        let mut chip8 = Chip8::new(self.options.clone());
+        chip8.restore(&self.snapshot());
+        chip8
+    }
+
+    /// Skip the next instruction, including the immediate operand of an
+    /// [`Instruction::SetIndexLong`]. Like [`fetch`](Chip8::fetch), this wraps around at the end
+    /// of memory.
+    fn skip(&mut self) {
+        let opcode = self.fetch();
+        if let Ok(Instruction::SetIndexLong) = Instruction::try_from(opcode) {
+            self.pc = self.pc.wrapping_add(2);
+        }
+    }
+}
+
+/// The address of the small font, followed by the big font, for a CHIP-8 program that starts at
+/// the given address, in memory of the given size.
+#[allow(clippy::cast_possible_truncation)]
+fn font_address(start_address: u16, memory_size: usize) -> u16 {
+    if start_address < FONT_ADDRESS + FONT_LENGTH {
+        (memory_size - HIGH_FONT_OFFSET) as u16
+    } else {
+        FONT_ADDRESS
+    }
+}
+
+fn write_font(memory: &mut [u8], address: u16, font_style: FontStyle) {
+    let (font, big_font) = &font_style.get_font_data();
+    let address = address as usize;
+
+    memory[address..(address + font.len())].clone_from_slice(&font[..]);
+
+    if let Some(big_font) = big_font {
+        memory[(address + font.len())..(address + font.len() + big_font.len())]
+            .clone_from_slice(&big_font[..]);
+    }
+}
+
+/// Read a byte of memory.
+///
+/// Addresses past the end of memory wrap around to the start, so every `u16` address is valid,
+/// even with [4 KB of memory](MemoryModel::Small).
+impl std::ops::Index<u16> for Chip8 {
+    type Output = u8;
+
+    fn index(&self, addr: u16) -> &u8 {
+        &self.memory[self.memory.wrap_address(addr)]
+    }
+}
+
+/// Write a byte of memory.
+///
+/// Addresses past the end of memory wrap around to the start, so every `u16` address is valid,
+/// even with [4 KB of memory](MemoryModel::Small).
+impl std::ops::IndexMut<u16> for Chip8 {
+    fn index_mut(&mut self, addr: u16) -> &mut u8 {
+        let index = self.memory.wrap_address(addr);
+        &mut self.memory[index]
+    }
+}
+
+/// A one-line summary of the CPU state, the same as [`dump_registers`](Chip8::dump_registers),
+/// followed by ` [hires]` in high-resolution mode.
+impl std::fmt::Display for Chip8 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.dump_registers())?;
+        if self.display.hires {
+            write!(f, " [hires]")?;
+        }
+        Ok(())
+    }
+}
+
+/// Interpreters are equal if their registers, timers, stack pointer and display buffer are, and, if
+/// [`hash_memory`](Chip8::hash_memory) is set, their memory.
+impl PartialEq for Chip8 {
+    fn eq(&self, other: &Self) -> bool {
+        self.pc == other.pc
+            && self.sp == other.sp
+            && self.v == other.v
+            && self.i == other.i
+            && self.flags == other.flags
+            && self.delay == other.delay
+            && self.sound == other.sound
+            && self.display.display == other.display.display
+            && self.hash_memory == other.hash_memory
+            && (!self.hash_memory || self.memory == other.memory)
+    }
+}
+
+impl Eq for Chip8 {}
+
+impl std::hash::Hash for Chip8 {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.pc.hash(state);
+        self.sp.hash(state);
+        self.v.hash(state);
+        self.i.hash(state);
+        self.flags.hash(state);
+        self.delay.hash(state);
+        self.sound.hash(state);
+        self.display.display.hash(state);
+        self.hash_memory.hash(state);
+        if self.hash_memory {
+            self.memory.hash(state);
+        }
     }
 }
 
@@ -502,3 +2190,15 @@ impl Default for Chip8 {
         Self::new(Options::default())
     }
 }
+
+impl From<Options> for Chip8 {
+    fn from(options: Options) -> Self {
+        Self::new(options)
+    }
+}
+
+impl From<&Options> for Chip8 {
+    fn from(options: &Options) -> Self {
+        Self::new(options.clone())
+    }
+}
@@ -0,0 +1,139 @@
+use std::ops::{Deref, DerefMut};
+
+/// The CHIP-8 memory, in one of two sizes.
+///
+/// The memory dereferences to a byte slice, whose length is the size of the memory.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub enum MemoryModel {
+    /// 4 KB of memory, like the original CHIP-8 on the COSMAC VIP, for strict compatibility or
+    /// to save memory on embedded targets.
+    Small(Box<[u8; 4096]>),
+    /// 64 KB of memory, the whole 16-bit address space, like XO-CHIP.
+    Large(Box<[u8; 65536]>),
+}
+
+impl MemoryModel {
+    /// 4 KB of zeroed memory.
+    #[must_use]
+    pub fn small() -> MemoryModel {
+        MemoryModel::Small(Box::new([0; 4096]))
+    }
+
+    /// 64 KB of zeroed memory.
+    #[must_use]
+    pub fn large() -> MemoryModel {
+        MemoryModel::Large(Box::new([0; 65536]))
+    }
+
+    /// The position in memory of an address. With 4 KB of memory, addresses from `0x1000` and up
+    /// wrap around to the start of memory, like on a COSMAC VIP whose address decoding ignores
+    /// the upper address lines, so every `u16` address is valid.
+    #[must_use]
+    pub fn wrap_address(&self, addr: u16) -> usize {
+        usize::from(addr) % self.len()
+    }
+}
+
+impl Clone for MemoryModel {
+    fn clone(&self) -> Self {
+        match self {
+            MemoryModel::Small(memory) => MemoryModel::Small(memory.clone()),
+            MemoryModel::Large(memory) => MemoryModel::Large(memory.clone()),
+        }
+    }
+
+    // Copy into the existing buffer when the sizes match, instead of allocating a new one
+    fn clone_from(&mut self, source: &Self) {
+        match (self, source) {
+            (MemoryModel::Small(memory), MemoryModel::Small(source)) => **memory = **source,
+            (MemoryModel::Large(memory), MemoryModel::Large(source)) => **memory = **source,
+            (memory, source) => *memory = source.clone(),
+        }
+    }
+}
+
+impl Default for MemoryModel {
+    fn default() -> Self {
+        MemoryModel::large()
+    }
+}
+
+impl Deref for MemoryModel {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            MemoryModel::Small(memory) => &memory[..],
+            MemoryModel::Large(memory) => &memory[..],
+        }
+    }
+}
+
+impl DerefMut for MemoryModel {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        match self {
+            MemoryModel::Small(memory) => &mut memory[..],
+            MemoryModel::Large(memory) => &mut memory[..],
+        }
+    }
+}
+
+/// A read-only view of the CHIP-8 memory, returned by
+/// [`Chip8::memory_view`](crate::Chip8::memory_view).
+///
+/// # Examples
+///
+/// ```
+/// # use deca::Chip8;
+/// let mut chip8 = Chip8::default();
+/// chip8.read_rom(&[0x12, 0x34]);
+/// let memory = chip8.memory_view();
+/// assert_eq!(memory.read_byte(0x200), 0x12);
+/// assert_eq!(memory.read_u16_be(0x200), 0x1234);
+/// assert_eq!(memory.slice(0x200, 2), Some(&[0x12, 0x34][..]));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryView<'a>(pub &'a [u8]);
+
+impl MemoryView<'_> {
+    /// Read the byte at an address. Addresses past the end of memory wrap around to the start.
+    #[must_use]
+    pub fn read_byte(&self, addr: u16) -> u8 {
+        self.0[addr as usize % self.0.len()]
+    }
+
+    /// Read the big-endian 16-bit word at an address, like an opcode. This wraps around to the
+    /// start of memory if `addr` is the last address in memory.
+    #[must_use]
+    pub fn read_u16_be(&self, addr: u16) -> u16 {
+        u16::from_be_bytes([self.read_byte(addr), self.read_byte(addr.wrapping_add(1))])
+    }
+
+    /// A slice of `len` bytes of memory starting at an address, or `None` if it extends past the
+    /// end of memory.
+    #[must_use]
+    pub fn slice(&self, start: u16, len: usize) -> Option<&[u8]> {
+        self.0
+            .get(start as usize..(start as usize).checked_add(len)?)
+    }
+}
+
+/// Statistics about how the CHIP-8 memory is used, returned by
+/// [`Chip8::memory_statistics`](crate::Chip8::memory_statistics).
+///
+/// The regions are given as `(start, end)` addresses, where `end` is just past the region.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryStats {
+    /// The region where the small and big fonts are loaded.
+    pub font_region: (u16, u16),
+    /// The region where the CHIP-8 program is loaded. Its end can be `0x10000`, if the program
+    /// fills the rest of memory.
+    pub rom_region: (usize, usize),
+    /// The number of bytes in memory that are zero.
+    pub zero_bytes: usize,
+    /// The number of bytes in memory that aren't zero.
+    pub total_nonzero: usize,
+    /// The Shannon entropy of the byte values in memory, in bits per byte, from 0 to 8. Code
+    /// usually has a higher entropy than data like sprites.
+    pub entropy: f64,
+}
@@ -0,0 +1,58 @@
+use ux::u4;
+
+macro_rules! register_getters {
+    ($($name:ident $index:literal),*) => {
+        $(
+            #[doc = concat!("The value of register V", stringify!($index), ".")]
+            #[must_use]
+            pub fn $name(&self) -> u8 {
+                self.0[$index]
+            }
+        )*
+    };
+}
+
+/// A read-only view of the variable registers, returned by
+/// [`Chip8::registers`](crate::Chip8::registers).
+///
+/// # Examples
+///
+/// ```
+/// # use deca::Chip8;
+/// let mut chip8 = Chip8::default();
+/// chip8.v[0] = 42;
+/// assert_eq!(chip8.registers().v0(), 42);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct RegisterView<'a>(pub &'a [u8; 16]);
+
+impl RegisterView<'_> {
+    register_getters!(
+        v0 0, v1 1, v2 2, v3 3, v4 4, v5 5, v6 6, v7 7, v8 8, v9 9, va 0xA, vb 0xB, vc 0xC, vd 0xD,
+        ve 0xE, vf 0xF
+    );
+
+    /// The value of register VX.
+    #[must_use]
+    pub fn vx(&self, x: u4) -> u8 {
+        self.0[usize::from(u8::from(x))]
+    }
+}
+
+/// A mutable view of the variable registers, returned by
+/// [`Chip8::registers_mut`](crate::Chip8::registers_mut).
+#[derive(Debug)]
+pub struct RegisterViewMut<'a>(pub &'a mut [u8; 16]);
+
+impl RegisterViewMut<'_> {
+    /// The value of register VX.
+    #[must_use]
+    pub fn vx(&self, x: u4) -> u8 {
+        self.0[usize::from(u8::from(x))]
+    }
+
+    /// Set the value of register VX.
+    pub fn set_vx(&mut self, x: u4, value: u8) {
+        self.0[usize::from(u8::from(x))] = value;
+    }
+}
@@ -0,0 +1,1843 @@
+#![allow(clippy::unwrap_used)]
+use deca::{
+    u4, AffectedRegisters, Chip8, Chip8Error, Chip8Extension, DecodeError, FontStyle, Instruction,
+    InstructionRegisters, MemoryModel, Options, Platform, RunResult, SoundEdge, StopReason,
+    TimerEvent,
+};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+struct SoundExtension;
+
+impl Chip8Extension for SoundExtension {
+    fn execute(
+        &mut self,
+        chip8: &mut Chip8,
+        instruction: Instruction,
+    ) -> Option<Result<(), Chip8Error>> {
+        match instruction {
+            Instruction::SoundStuff => {
+                chip8.sound = 42;
+                Some(Ok(()))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn extension_handles_unimplemented_instruction() {
+    let mut chip8 = Chip8::default();
+    assert!(matches!(
+        chip8.execute(Instruction::SoundStuff),
+        Err(Chip8Error::UnimplementedInstruction(
+            Instruction::SoundStuff
+        ))
+    ));
+
+    chip8.extensions.push(Box::new(SoundExtension));
+    chip8.execute(Instruction::SoundStuff).unwrap();
+    assert_eq!(chip8.sound, 42);
+    assert!(matches!(
+        chip8.execute(Instruction::SoundStuffTwo),
+        Err(Chip8Error::UnimplementedInstruction(
+            Instruction::SoundStuffTwo
+        ))
+    ));
+}
+
+#[test]
+fn sound_edges() {
+    let mut chip8 = Chip8::default();
+    assert!(!chip8.sound_active());
+    assert_eq!(chip8.sound_edge(), SoundEdge::Unchanged);
+
+    chip8.sound = 2;
+    assert!(chip8.sound_active());
+    assert_eq!(chip8.sound_edge(), SoundEdge::Started);
+
+    chip8.tick_timers();
+    assert_eq!(chip8.sound_edge(), SoundEdge::Unchanged);
+
+    chip8.tick_timers();
+    assert!(!chip8.sound_active());
+    assert_eq!(chip8.sound_edge(), SoundEdge::Stopped);
+
+    chip8.tick_timers();
+    assert_eq!(chip8.sound_edge(), SoundEdge::Unchanged);
+}
+
+#[test]
+fn rom_start_address() {
+    let mut chip8 = Chip8::default();
+    assert_eq!(chip8.rom_start_address(), 0x200);
+    assert_eq!(chip8.effective_memory_end(), 0x200);
+    chip8.read_rom(&[0x00, 0xE0, 0x12, 0x02]);
+    assert_eq!(chip8.effective_memory_end(), 0x204);
+
+    // A ROM that fills the rest of memory ends past the last address
+    chip8.read_rom(&[0; 0x10000]);
+    assert_eq!(chip8.effective_memory_end(), 0x10000);
+    assert_eq!(chip8.memory_statistics().rom_region, (0x200, 0x10000));
+}
+
+#[test]
+fn set_individual_quirks() {
+    let mut chip8 = Chip8::default();
+    chip8.set_load_store_quirk(true);
+    chip8.set_vblank_quirk(false);
+    assert_eq!(chip8.options.quirks.load_store, Some(true));
+    assert_eq!(chip8.options.quirks.vblank, Some(false));
+
+    chip8.set_quirk(|quirks| quirks.load_store = None);
+    assert_eq!(chip8.options.quirks.load_store, None);
+    assert_eq!(chip8.options.quirks.vblank, Some(false));
+}
+
+#[test]
+fn stack_trace_in_errors() {
+    let mut chip8 = Chip8::default();
+    // 0x200: call 0x204; 0x202: return; 0x204: return
+    chip8.read_rom(&[0x22, 0x04, 0x00, 0xEE, 0x00, 0xEE]);
+    chip8.run(2).unwrap();
+    assert!(matches!(
+        chip8.run(1),
+        Err(Chip8Error::StackUnderflow { stack_frames }) if stack_frames.is_empty()
+    ));
+
+    let mut chip8 = Chip8::default();
+    chip8.stack_trace_in_errors = true;
+    // 0x200: call 0x202; 0x202: call 0x202 (recurses until the stack overflows)
+    chip8.read_rom(&[0x22, 0x02, 0x22, 0x02]);
+    match chip8.run(17) {
+        Err(Chip8Error::StackOverflow { stack_frames }) => {
+            let mut expected = vec![0x204; 17];
+            expected[16] = 0x202;
+            assert_eq!(stack_frames, expected);
+        }
+        result => panic!("Expected stack overflow, got {result:?}"),
+    }
+}
+
+#[test]
+fn execution_limit() {
+    let mut chip8 = Chip8::default().with_execution_limit(10);
+    // 0x200: jump 0x200
+    chip8.read_rom(&[0x12, 0x00]);
+    assert!(matches!(
+        chip8.run(100),
+        Err(Chip8Error::ExecutionLimitExceeded(10))
+    ));
+    assert_eq!(chip8.cycles, 10);
+    assert!(matches!(
+        chip8.step(),
+        Err(Chip8Error::ExecutionLimitExceeded(10))
+    ));
+    assert_eq!(chip8.cycles, 10);
+}
+
+#[test]
+fn loop_detection() {
+    let mut chip8 = Chip8::default();
+    chip8.loop_detection = true;
+    chip8.loop_detection_threshold = 10;
+    // 0x200: v0 := 60; 0x202: delay := v0
+    // 0x204: v0 := delay; 0x206: if v0 != 0 then jump 0x204
+    // 0x20A: jump 0x20A
+    chip8.read_rom(&[
+        0x60, 0x3C, 0xF0, 0x15, 0xF0, 0x07, 0x30, 0x00, 0x12, 0x04, 0x12, 0x0A,
+    ]);
+    for _ in 0..60 {
+        chip8.run(10).unwrap();
+    }
+    let mut frames = 0;
+    let pc = loop {
+        match chip8.run(10) {
+            Ok(_) => frames += 1,
+            Err(Chip8Error::InfiniteLoopDetected { pc }) => break pc,
+            Err(e) => panic!("Unexpected error {e}"),
+        }
+        assert!(frames < 20);
+    };
+    assert_eq!(pc, 0x20A);
+
+    // Waiting for a key press isn't an infinite loop
+    let mut chip8 = Chip8::default();
+    chip8.loop_detection = true;
+    chip8.loop_detection_threshold = 10;
+    // 0x200: v0 := key
+    chip8.read_rom(&[0xF0, 0x0A]);
+    for _ in 0..100 {
+        chip8.run(10).unwrap();
+    }
+}
+
+#[test]
+fn snapshot_round_trip() {
+    let mut chip8 = Chip8::default();
+    // 0x200: v0 := 5; 0x202: delay := v0; 0x204: sound := v0; 0x206: call 0x20A
+    // 0x20A: i := 0x208; 0x20C: sprite v0 v0 1; 0x20E: v1 += 1; 0x210: jump 0x20C
+    chip8.read_rom(&[
+        0x60, 0x05, 0xF0, 0x15, 0xF0, 0x18, 0x22, 0x0A, 0xFF, 0x00, 0xA2, 0x08, 0xD0, 0x01, 0x71,
+        0x01, 0x12, 0x0C,
+    ]);
+    chip8.run(6).unwrap();
+    let snapshot = chip8.snapshot();
+    let (pc, v, stack, sp) = (chip8.pc, chip8.v, chip8.stack.clone(), chip8.sp);
+    let (delay, sound) = (chip8.delay, chip8.sound);
+    let display = chip8.display.clone();
+
+    chip8.run(7).unwrap();
+    chip8.run(7).unwrap();
+    assert_ne!(chip8.v, v);
+
+    chip8.restore(&snapshot);
+    assert_eq!(chip8.pc, pc);
+    assert_eq!(chip8.v, v);
+    assert_eq!(chip8.stack, stack);
+    assert_eq!(chip8.sp, sp);
+    assert_eq!((chip8.delay, chip8.sound), (delay, sound));
+    chip8.display.assert_identical_to(&display);
+}
+
+fn vf_conflict(opcode: u16, vf_conflict_quirk: Option<bool>) -> u8 {
+    let mut chip8 = Chip8::default();
+    chip8.vf_conflict_quirk = vf_conflict_quirk;
+    chip8.v[0xF] = 0x80;
+    let instruction = chip8.decode(opcode).unwrap();
+    chip8.execute(instruction).unwrap();
+    chip8.v[0xF]
+}
+
+#[test]
+fn vf_conflict_quirk() {
+    // vF += vF
+    assert_eq!(vf_conflict(0x8FF4, None), 1);
+    assert_eq!(vf_conflict(0x8FF4, Some(true)), 1);
+    assert_eq!(vf_conflict(0x8FF4, Some(false)), 2);
+    // vF -= vF
+    assert_eq!(vf_conflict(0x8FF5, None), 1);
+    assert_eq!(vf_conflict(0x8FF5, Some(true)), 1);
+    assert_eq!(vf_conflict(0x8FF5, Some(false)), 0);
+    // vF =- vF
+    assert_eq!(vf_conflict(0x8FF7, None), 1);
+    assert_eq!(vf_conflict(0x8FF7, Some(true)), 1);
+    assert_eq!(vf_conflict(0x8FF7, Some(false)), 0);
+    // vF >>= vF
+    assert_eq!(vf_conflict(0x8FF6, None), 0);
+    assert_eq!(vf_conflict(0x8FF6, Some(true)), 0);
+    assert_eq!(vf_conflict(0x8FF6, Some(false)), 0x40);
+    // vF <<= vF
+    assert_eq!(vf_conflict(0x8FFE, None), 1);
+    assert_eq!(vf_conflict(0x8FFE, Some(true)), 1);
+    assert_eq!(vf_conflict(0x8FFE, Some(false)), 0);
+}
+
+#[test]
+fn delay_timer_wrapping() {
+    let mut chip8 = Chip8::default();
+    chip8.delay = 1;
+    chip8.tick_timers();
+    assert_eq!(chip8.delay, 0);
+    chip8.tick_timers();
+    assert_eq!(chip8.delay, 0);
+
+    chip8.set_delay_wrap_quirk(true);
+    chip8.delay = 1;
+    chip8.tick_timers();
+    assert_eq!(chip8.delay, 0);
+    chip8.tick_timers();
+    assert_eq!(chip8.delay, 255);
+}
+
+#[test]
+fn affected_registers() {
+    const I: usize = InstructionRegisters::INDEX;
+    let registers = |opcode| Instruction::try_from(opcode).unwrap().affected_registers();
+    let set = |registers: [bool; 17]| (0..17).filter(|&n| registers[n]).collect::<Vec<usize>>();
+
+    // v1 += v2
+    let r = registers(0x8124);
+    assert_eq!(set(r.reads), vec![1, 2]);
+    assert_eq!(set(r.writes), vec![1, 0xF]);
+    // v3 := 0x42
+    let r = registers(0x6342);
+    assert_eq!(set(r.reads), vec![]);
+    assert_eq!(set(r.writes), vec![3]);
+    // sprite v4 v5 1
+    let r = registers(0xD451);
+    assert_eq!(set(r.reads), vec![4, 5, I]);
+    assert_eq!(set(r.writes), vec![0xF]);
+    // save v2
+    let r = registers(0xF255);
+    assert_eq!(set(r.reads), vec![0, 1, 2, I]);
+    assert_eq!(set(r.writes), vec![I]);
+    // load v3 - v1
+    let r = registers(0x5313);
+    assert_eq!(set(r.reads), vec![I]);
+    assert_eq!(set(r.writes), vec![1, 2, 3]);
+    // clear
+    assert_eq!(registers(0x00E0), InstructionRegisters::default());
+}
+
+#[test]
+fn instruction_at() {
+    let mut chip8 = Chip8::default();
+    // 0x200: clear; 0x202: i := long 0x1234; 0x206: invalid
+    chip8.read_rom(&[0x00, 0xE0, 0xF0, 0x00, 0x12, 0x34, 0xFF, 0xFF]);
+    assert_eq!(chip8.opcode_at(0x200), 0x00E0);
+    assert_eq!(chip8.opcode_at(0x202), 0xF000);
+    assert!(matches!(
+        chip8.instruction_at(0x200),
+        (0x00E0, Ok(Instruction::Clear))
+    ));
+    assert!(matches!(
+        chip8.instruction_at(0x202),
+        (0xF000, Ok(Instruction::SetIndex(0x1234)))
+    ));
+    assert!(matches!(
+        chip8.instruction_at(0x206),
+        (0xFFFF, Err(Chip8Error::Decode(_)))
+    ));
+    assert_eq!(chip8.pc, 0x200);
+}
+
+#[test]
+fn construct_from_options() {
+    let options = Options {
+        start_address: Some(0x600),
+        ..Default::default()
+    };
+
+    let chip8 = Chip8::new(options.clone());
+    assert_eq!(chip8.options, options);
+    assert_eq!(chip8.pc, 0x600);
+    let chip8 = Chip8::with_options(options.clone());
+    assert_eq!(chip8.options, options);
+    assert_eq!(chip8.pc, 0x600);
+    let chip8 = Chip8::from(&options);
+    assert_eq!(chip8.options, options);
+    assert_eq!(chip8.pc, 0x600);
+    let chip8: Chip8 = options.clone().into();
+    assert_eq!(chip8.options, options);
+    assert_eq!(chip8.pc, 0x600);
+}
+
+#[test]
+fn display_frame_number() {
+    let mut chip8 = Chip8::default();
+    // 0x200: sprite v0 v0 1; 0x202: jump 0x202
+    chip8.read_rom(&[0xD0, 0x01, 0x12, 0x02]);
+    chip8.i = 0x50;
+    assert_eq!(chip8.display.frame_number, 0);
+    chip8.run(1).unwrap();
+    assert_eq!(chip8.display.frame_number, 1);
+    assert_eq!(chip8.display.dirty_since_frame(), 1);
+    chip8.run(1).unwrap();
+    chip8.run(1).unwrap();
+    assert_eq!(chip8.display.frame_number, 3);
+    assert_eq!(chip8.display.dirty_since_frame(), 1);
+}
+
+#[test]
+fn timer_events() {
+    let mut chip8 = Chip8::default();
+    let mut events = Vec::new();
+    chip8.tick_timers_with_callback(|event| events.push(event));
+    assert!(events.is_empty());
+
+    chip8.sound = 2;
+    chip8.delay = 1;
+    chip8.tick_timers_with_callback(|event| events.push(event));
+    assert_eq!(
+        events,
+        [TimerEvent::SoundStarted, TimerEvent::DelayReachedZero]
+    );
+
+    events.clear();
+    chip8.tick_timers_with_callback(|event| events.push(event));
+    assert_eq!(events, [TimerEvent::SoundStopped]);
+
+    events.clear();
+    chip8.tick_timers_with_callback(|event| events.push(event));
+    assert!(events.is_empty());
+}
+
+#[test]
+fn index_memory() {
+    let mut chip8 = Chip8::default();
+    chip8[0x300u16] = 0x42;
+    chip8[0xFFFFu16] = 0x24;
+    assert_eq!(chip8[0x300u16], 0x42);
+    assert_eq!(chip8.memory_view().read_byte(0x300), 0x42);
+    assert_eq!(chip8[0xFFFFu16], 0x24);
+}
+
+#[test]
+fn chip8_is_send() {
+    fn assert_send<T: Send>() {}
+    assert_send::<Chip8>();
+
+    let mut chip8 = Chip8::default();
+    chip8.extensions.push(Box::new(SoundExtension));
+    let chip8 = std::thread::spawn(move || {
+        chip8.execute(Instruction::SoundStuff).unwrap();
+        chip8
+    })
+    .join()
+    .unwrap();
+    assert_eq!(chip8.sound, 42);
+}
+
+#[test]
+fn available_memory() {
+    let mut chip8 = Chip8::default();
+    assert_eq!(chip8.available_memory(), 0x10000 - 0x200);
+    chip8.read_rom(&[0; 0x100]);
+    assert_eq!(chip8.available_memory(), 0x10000 - 0x300);
+    assert!(!chip8.stack_area_overlap_risk());
+    chip8.read_rom(&[0; 0xCA0]);
+    assert!(!chip8.stack_area_overlap_risk());
+    chip8.read_rom(&[0; 0xCA1]);
+    assert!(chip8.stack_area_overlap_risk());
+}
+
+#[test]
+fn set_index_long_wraps_around() {
+    let mut chip8 = Chip8::default();
+    // 0xFFFE: i := long 0x1234, with the address at 0x0000
+    chip8[0xFFFEu16] = 0xF0;
+    chip8[0xFFFFu16] = 0x00;
+    chip8[0x0000u16] = 0x12;
+    chip8[0x0001u16] = 0x34;
+    assert!(matches!(
+        chip8.instruction_at(0xFFFE),
+        (0xF000, Ok(Instruction::SetIndex(0x1234)))
+    ));
+    chip8.pc = 0xFFFE;
+    chip8.step().unwrap();
+    assert_eq!(chip8.i, 0x1234);
+    assert_eq!(chip8.pc, 0x0002);
+
+    // 0xFFFC: if v0 == 1 then (skips the long instruction at 0xFFFE, since v0 is 0)
+    chip8.i = 0;
+    chip8[0xFFFCu16] = 0x40;
+    chip8[0xFFFDu16] = 0x01;
+    chip8.pc = 0xFFFC;
+    chip8.step().unwrap();
+    assert_eq!(chip8.i, 0);
+    assert_eq!(chip8.pc, 0x0002);
+}
+
+#[test]
+fn decode_error_keeps_pc() {
+    let mut chip8 = Chip8::default();
+    // 0xFFFE: i := long, with the address wrapping around to 0x0000
+    chip8[0xFFFEu16] = 0xF0;
+    chip8[0xFFFFu16] = 0x00;
+    chip8.pc = 0xFFFE;
+    let opcode = chip8.fetch();
+    assert!(matches!(
+        chip8.decode(opcode),
+        Ok(Instruction::SetIndex(0x0000))
+    ));
+    assert_eq!(chip8.pc, 0x0002);
+
+    for _ in 0..2 {
+        assert!(matches!(
+            chip8.decode(0xFFFF),
+            Err(Chip8Error::Decode(DecodeError::UnknownOpcode {
+                opcode: 0xFFFF,
+                pc: 0x0000
+            }))
+        ));
+        assert_eq!(chip8.pc, 0x0002);
+    }
+}
+
+#[test]
+fn stack_size() {
+    // 0x200: call 0x202; 0x202: call 0x202 (recurses until the stack overflows)
+    let rom = [0x22, 0x02, 0x22, 0x02];
+
+    let mut chip8 = Chip8::default().with_stack_size(2);
+    chip8.read_rom(&rom);
+    chip8.run(2).unwrap();
+    assert_eq!(chip8.sp, 2);
+    assert!(matches!(
+        chip8.run(1),
+        Err(Chip8Error::StackOverflow { .. })
+    ));
+
+    let mut chip8 = Chip8::default().with_stack_size(32);
+    chip8.read_rom(&rom);
+    chip8.run(32).unwrap();
+    assert_eq!(chip8.sp, 32);
+    assert!(matches!(
+        chip8.run(1),
+        Err(Chip8Error::StackOverflow { .. })
+    ));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn load_rom_file() {
+    fn load(chip8: &mut Chip8, path: &str) -> Result<(), Chip8Error> {
+        chip8.load_rom_file(path)?;
+        Ok(())
+    }
+
+    let mut chip8 = Chip8::default();
+    load(
+        &mut chip8,
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/test_roms/BC_test.ch8"),
+    )
+    .unwrap();
+    assert_eq!(
+        chip8.memory_view().slice(0x200, chip8.rom_length).unwrap(),
+        include_bytes!("test_roms/BC_test.ch8")
+    );
+    let error = load(&mut chip8, "does/not/exist.ch8").unwrap_err();
+    assert!(matches!(error, Chip8Error::Io(_)));
+    let source = std::error::Error::source(&error).unwrap();
+    assert!(source.downcast_ref::<std::io::Error>().is_some());
+}
+
+#[test]
+fn run_result() {
+    let mut chip8 = Chip8::default();
+    // 0x200: sprite v0 v0 1; 0x202: jump 0x200
+    chip8.read_rom(&[0xD0, 0x01, 0x12, 0x00]);
+    assert_eq!(
+        chip8.run(10).unwrap(),
+        RunResult {
+            instructions_executed: 10,
+            stop_reason: StopReason::TickLimit,
+        }
+    );
+
+    assert_eq!(chip8.draw_calls, 5);
+    assert_eq!(chip8.vblank_count, 0);
+
+    chip8.set_vblank_quirk(true);
+    assert_eq!(
+        chip8.run(10).unwrap(),
+        RunResult {
+            instructions_executed: 1,
+            stop_reason: StopReason::VBlank,
+        }
+    );
+    assert_eq!(chip8.run(10).unwrap().instructions_executed, 2);
+    assert_eq!(chip8.draw_calls, 7);
+    assert_eq!(chip8.vblank_count, 2);
+
+    assert!(matches!(chip8.step().unwrap(), Instruction::Jump(_)));
+    assert!(matches!(chip8.step().unwrap(), Instruction::Draw(..)));
+}
+
+#[test]
+fn draw_calls_per_instruction_and_plane() {
+    let mut chip8 = Chip8::default();
+    // 0x200: plane 3; 0x202: sprite v0 v0 1; 0x204: plane 0; 0x206: sprite v0 v0 1
+    chip8.read_rom(&[0xF3, 0x01, 0xD0, 0x01, 0xF0, 0x01, 0xD0, 0x01]);
+    chip8.run(2).unwrap();
+    assert_eq!(chip8.draw_calls, 1);
+    assert_eq!(chip8.display.total_draw_calls, 2);
+    chip8.run(2).unwrap();
+    assert_eq!(chip8.draw_calls, 2);
+    assert_eq!(chip8.display.total_draw_calls, 2);
+}
+
+#[test]
+fn register_views() {
+    let mut chip8 = Chip8::default();
+    chip8.registers_mut().set_vx(u4::new(0xA), 42);
+    chip8.v[0xF] = 1;
+    assert_eq!(chip8.v[0xA], 42);
+    assert_eq!(chip8.registers().va(), 42);
+    assert_eq!(chip8.registers().vf(), 1);
+    assert_eq!(chip8.registers().v0(), 0);
+    assert_eq!(chip8.registers().vx(u4::new(0xA)), 42);
+    assert_eq!(chip8.registers_mut().vx(u4::new(0xF)), 1);
+}
+
+#[test]
+fn read_rom_clears_previous_rom() {
+    let mut chip8 = Chip8::default();
+    chip8.read_rom(&[0xFF; 100]);
+    chip8.read_rom(&[0xAA; 50]);
+    let memory = chip8.memory_view();
+    assert!(memory
+        .slice(0x200, 50)
+        .unwrap()
+        .iter()
+        .all(|&byte| byte == 0xAA));
+    assert!(memory
+        .slice(0x232, 50)
+        .unwrap()
+        .iter()
+        .all(|&byte| byte == 0));
+    assert_eq!(chip8.rom_length, 50);
+}
+
+#[test]
+fn read_rom_after_moving_start_address() {
+    let mut chip8 = Chip8::default();
+    chip8.read_rom(&[0xFF; 0x100]);
+    chip8.options.start_address = Some(0xFF80);
+    assert_eq!(chip8.read_rom(&[1]), 1);
+    assert_eq!(chip8[0xFF80], 1);
+    assert!(chip8.memory_dump()[0xFF81..].iter().all(|&byte| byte == 0));
+    assert_eq!(chip8.rom_length, 1);
+}
+
+#[test]
+fn collision_in_any_plane() {
+    let mut chip8 = Chip8::default();
+    // 0x200: plane 1; 0x202: i := 0x20C; 0x204: sprite v0 v0 1
+    // 0x206: plane 3; 0x208: i := 0x20D; 0x20A: sprite v0 v0 1
+    // 0x20C: 0x80 (plane 1 data); 0x20D: 0x80 (plane 1 data); 0x20E: 0x00 (plane 2 data)
+    chip8.read_rom(&[
+        0xF1, 0x01, 0xA2, 0x0C, 0xD0, 0x01, 0xF3, 0x01, 0xA2, 0x0D, 0xD0, 0x01, 0x80, 0x80, 0x00,
+    ]);
+    chip8.run(3).unwrap();
+    assert_eq!(chip8.v[0xF], 0);
+    chip8.run(3).unwrap();
+    assert_eq!(chip8.v[0xF], 1);
+    assert_eq!(chip8.display.get_pixel(0, 0), 0);
+}
+
+#[test]
+fn construct_from_platform() {
+    for (platform, octopt_platform) in [
+        (Platform::Chip8, octopt::Platform::Vip),
+        (Platform::SuperChip, octopt::Platform::Schip),
+        (Platform::XoChip, octopt::Platform::Xochip),
+    ] {
+        let chip8 = Chip8::new(platform);
+        assert_eq!(chip8.options, Options::new(octopt_platform));
+    }
+}
+
+#[test]
+fn toggle_load_store_quirk() {
+    // Store v0 at 0x300 and return the index register afterwards
+    fn store(chip8: &mut Chip8) -> u16 {
+        chip8.i = 0x300;
+        let instruction = chip8.decode(0xF055).unwrap();
+        chip8.execute(instruction).unwrap();
+        chip8.i
+    }
+
+    let mut chip8 = Chip8::default();
+    assert_eq!(chip8.options.quirks.load_store, None);
+    assert_eq!(store(&mut chip8), 0x301);
+
+    chip8.execute(Instruction::ToggleLoadStoreQuirk).unwrap();
+    assert_eq!(chip8.options.quirks.load_store, Some(true));
+    assert_eq!(store(&mut chip8), 0x300);
+
+    chip8.execute(Instruction::ToggleLoadStoreQuirk).unwrap();
+    assert_eq!(chip8.options.quirks.load_store, Some(false));
+    assert_eq!(store(&mut chip8), 0x301);
+}
+
+#[test]
+fn hash_and_compare() {
+    fn hash(chip8: &Chip8) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        chip8.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    // 0x200: v0 += 1; 0x202: i := 0x20A; 0x204: sprite v0 v0 1; 0x206: save v0; 0x208: jump 0x200
+    let rom = [0x70, 0x01, 0xA2, 0x0A, 0xD0, 0x01, 0xF0, 0x55, 0x12, 0x00];
+    let mut a = Chip8::default();
+    let mut b = Chip8::default();
+    a.read_rom(&rom);
+    b.read_rom(&rom);
+    a.run(23).unwrap();
+    b.run(23).unwrap();
+    assert!(a == b);
+    assert_eq!(hash(&a), hash(&b));
+
+    b.step().unwrap();
+    assert!(a != b);
+    assert_ne!(hash(&a), hash(&b));
+
+    // Memory is only compared when asked for
+    let mut b = Chip8::default();
+    b.read_rom(&rom);
+    b.run(23).unwrap();
+    b[0x1000u16] = 1;
+    assert!(a == b);
+    a.hash_memory = true;
+    b.hash_memory = true;
+    assert!(a != b);
+    assert_ne!(hash(&a), hash(&b));
+}
+
+#[test]
+fn load_rom_bytes() {
+    let chip8 = Chip8::load_rom_bytes(&[0x12, 0x34]).unwrap();
+    assert_eq!(chip8.opcode_at(0x200), 0x1234);
+    assert_eq!(chip8.rom_length, 2);
+    assert!(Chip8::load_rom_bytes(&[0; 0x10000 - 0x200]).is_ok());
+    assert!(matches!(
+        Chip8::load_rom_bytes(&[0; 0x10000 - 0x1FF]),
+        Err(Chip8Error::RomTooLarge {
+            size: 0xFE01,
+            max_size: 0xFE00
+        })
+    ));
+}
+
+#[test]
+fn memory_view() {
+    let mut chip8 = Chip8::default();
+    chip8[0xFFFFu16] = 0x12;
+    chip8[0x0000u16] = 0x34;
+    let memory = chip8.memory_view();
+    assert_eq!(memory.read_byte(0xFFFF), 0x12);
+    assert_eq!(memory.read_u16_be(0xFFFF), 0x1234);
+    assert_eq!(memory.slice(0xFFFF, 1), Some(&[0x12][..]));
+    assert_eq!(memory.slice(0xFFFF, 2), None);
+    assert_eq!(memory.slice(0, 0), Some(&[][..]));
+}
+
+#[test]
+fn sixteen_nested_calls() {
+    let mut chip8 = Chip8::default();
+    // 0x200: call 0x204; 0x202: jump 0x202
+    // 0x204 to 0x220: call the next address, 15 times; 0x222: call 0x222
+    let mut rom = vec![0x22, 0x04, 0x12, 0x02];
+    for n in 0..16u16 {
+        let address = (0x206 + n * 2).min(0x222);
+        rom.extend([0x20 | (address >> 8) as u8, address as u8]);
+    }
+    chip8.read_rom(&rom);
+
+    chip8.run(16).unwrap();
+    assert_eq!(chip8.sp, 16);
+    assert!(matches!(
+        chip8.step(),
+        Err(Chip8Error::StackOverflow { .. })
+    ));
+
+    let mut expected: Vec<u16> = (0x206..=0x222).step_by(2).rev().collect();
+    expected.push(0x202);
+    for pc in expected {
+        chip8.execute(Instruction::Return).unwrap();
+        assert_eq!(chip8.pc, pc);
+    }
+    assert!(matches!(
+        chip8.execute(Instruction::Return),
+        Err(Chip8Error::StackUnderflow { .. })
+    ));
+}
+
+#[test]
+fn draw_restores_active_plane() {
+    for plane in 0..=3 {
+        let mut chip8 = Chip8::default();
+        chip8.display.plane(plane).unwrap();
+        chip8.i = 0x50;
+        // sprite v0 v0 5
+        let instruction = chip8.decode(0xD005).unwrap();
+        chip8.execute(instruction).unwrap();
+        assert_eq!(chip8.display.active_plane, plane);
+        chip8.execute(instruction).unwrap();
+        assert_eq!(chip8.display.active_plane, plane);
+        assert_eq!(chip8.v[0xF], u8::from(plane != 0));
+    }
+}
+
+#[test]
+fn format_cpu_state() {
+    let mut chip8 = Chip8::default();
+    chip8.pc = 0x204;
+    chip8.i = 0x300;
+    for (n, v) in chip8.v.iter_mut().enumerate() {
+        *v = n as u8;
+    }
+    chip8.v[0xF] = 0xFF;
+    chip8.delay = 60;
+    chip8.stack[0] = 0x202;
+    chip8.stack[1] = 0x210;
+    chip8.sp = 2;
+    assert_eq!(
+        chip8.to_string(),
+        "V0=00 V1=01 V2=02 V3=03 V4=04 V5=05 V6=06 V7=07 V8=08 V9=09 VA=0A VB=0B VC=0C VD=0D \
+         VE=0E VF=FF I=0300 PC=0204 SP=2 DLY=60 SND=0"
+    );
+    assert_eq!(chip8.to_string(), chip8.dump_registers());
+    assert_eq!(
+        chip8.cpu_state_string(),
+        "PC: 0x0204  I: 0x0300  SP: 2
+V0: 00  V1: 01  V2: 02  V3: 03  V4: 04  V5: 05  V6: 06  V7: 07
+V8: 08  V9: 09  VA: 0A  VB: 0B  VC: 0C  VD: 0D  VE: 0E  VF: FF
+DLY: 60  SND: 0
+Stack: 0x0202 0x0210
+"
+    );
+
+    chip8.display.hires(false);
+    assert!(chip8.to_string().ends_with("DLY=60 SND=0 [hires]"));
+}
+
+#[test]
+fn block_key() {
+    // 0x200: v0 := key
+    let rom = [0xF0, 0x0A];
+    let mut chip8 = Chip8::default();
+    chip8.read_rom(&rom);
+    chip8.v[0] = 0xFF;
+    chip8.step().unwrap();
+    assert_eq!(chip8.pc, 0x200);
+    assert_eq!(chip8.v[0], 0xFF);
+    chip8.keyboard[7] = true;
+    chip8.step().unwrap();
+    assert_eq!(chip8.pc, 0x202);
+    assert_eq!(chip8.v[0], 7);
+
+    for (block_key_highest, expected) in [(None, 3), (Some(false), 3), (Some(true), 0xC)] {
+        let mut chip8 = Chip8::default();
+        chip8.block_key_highest = block_key_highest;
+        chip8.read_rom(&rom);
+        chip8.keyboard[3] = true;
+        chip8.keyboard[5] = true;
+        chip8.keyboard[0xC] = true;
+        chip8.step().unwrap();
+        assert_eq!(chip8.pc, 0x202);
+        assert_eq!(chip8.v[0], expected);
+    }
+}
+
+#[test]
+fn program_size_estimate() {
+    let mut chip8 = Chip8::default();
+    // 0x200: jump 0x206; 0x206: v0 := 1; 0x208: jump 0x204; 0x204: jump 0x204
+    chip8.read_rom(&[
+        0x12, 0x06, 0xFF, 0xFF, 0x12, 0x04, 0x60, 0x01, 0x12, 0x04, 0xFF, 0xFF,
+    ]);
+    assert_eq!(chip8.program_size_estimate(), None);
+    chip8.step().unwrap();
+    assert_eq!(chip8.program_size_estimate(), Some(2));
+    chip8.run(10).unwrap();
+    assert_eq!(chip8.pc_high_watermark, Some(0x208));
+    assert_eq!(chip8.program_size_estimate(), Some(10));
+}
+
+#[test]
+fn warm_load() {
+    let mut chip8 = Chip8::default();
+    // 0x200: hires; 0x202: v0 := 5; 0x204: saveflags v0; 0x206: i := 0x050
+    // 0x208: sprite v0 v0 1; 0x20A: call 0x20A
+    chip8.read_rom(&[
+        0x00, 0xFF, 0x60, 0x05, 0xF0, 0x75, 0xA0, 0x50, 0xD0, 0x01, 0x22, 0x0A,
+    ]);
+    chip8.delay = 10;
+    chip8.keyboard[4] = true;
+    chip8.run(7).unwrap();
+    assert_eq!(chip8.sp, 2);
+
+    chip8.warm_load(&[0x12, 0x00]).unwrap();
+    assert_eq!(chip8.pc, 0x200);
+    assert_eq!(chip8.opcode_at(0x200), 0x1200);
+    assert_eq!(chip8.opcode_at(0x202), 0x0000);
+    assert_eq!(chip8.v, [0; 16]);
+    assert_eq!((chip8.i, chip8.sp, chip8.delay, chip8.sound), (0, 0, 0, 0));
+    assert_eq!(chip8.flags[0], 5);
+    assert!(chip8.keyboard[4]);
+    assert!(chip8.display.hires);
+    assert_eq!(chip8.display.get_pixel(5, 5), 1);
+
+    assert!(matches!(
+        chip8.warm_load(&[0; 0x10000]),
+        Err(Chip8Error::RomTooLarge { .. })
+    ));
+    assert_eq!(chip8.opcode_at(0x200), 0x1200);
+}
+
+#[test]
+fn jump_relative() {
+    fn jump(opcode: u16, v: [u8; 16], jump0: bool) -> u16 {
+        let mut chip8 = Chip8::default();
+        chip8.set_jump0_quirk(jump0);
+        chip8.v = v;
+        let instruction = chip8.decode(opcode).unwrap();
+        chip8.execute(instruction).unwrap();
+        chip8.pc
+    }
+
+    let mut v = [0; 16];
+    v[0] = 0xFF;
+    v[3] = 0x02;
+    assert_eq!(jump(0xB000, v, false), 0x00FF);
+    assert_eq!(jump(0xBFFF, v, false), 0x10FE);
+    assert_eq!(jump(0xB3FF, v, false), 0x04FE);
+    // With the jump0 quirk, the highest nibble of the address selects the register
+    assert_eq!(jump(0xB3FF, v, true), 0x0401);
+    assert_eq!(jump(0xB0FF, v, true), 0x01FE);
+}
+
+#[test]
+fn draw_16x16_sprite() {
+    let mut chip8 = Chip8::default();
+    chip8.display.hires(false);
+    // Each row has an empty left half, and a right half with one pixel: the rightmost pixel on
+    // even rows and the leftmost pixel on odd rows. The last row is full.
+    let mut sprite = Vec::new();
+    for row in 0..16 {
+        sprite.push(0x00);
+        sprite.push(match row {
+            15 => 0xFF,
+            row if row % 2 == 0 => 0x01,
+            _ => 0x80,
+        });
+    }
+    for (n, byte) in sprite.iter().enumerate() {
+        chip8[0x300 + n as u16] = *byte;
+    }
+    chip8.i = 0x300;
+    chip8.v[0] = 10;
+    chip8.v[1] = 20;
+    // sprite v0 v1 0
+    let instruction = chip8.decode(0xD010).unwrap();
+    chip8.execute(instruction).unwrap();
+
+    for row in 0..15 {
+        let y = 20 + row;
+        for x in 10..18 {
+            assert_eq!(chip8.display.get_pixel(x, y), 0);
+        }
+        if row % 2 == 0 {
+            assert_eq!(chip8.display.get_pixel(25, y), 1);
+        } else {
+            assert_eq!(chip8.display.get_pixel(18, y), 1);
+        }
+    }
+    for x in 18..26 {
+        assert_eq!(chip8.display.get_pixel(x, 35), 1);
+    }
+    assert_eq!(chip8.display.active_pixel_count(), 15 + 8);
+}
+
+#[test]
+fn font_character_with_extra_fonts() {
+    let glyphs: [(FontStyle, [u8; 5]); 2] = [
+        (FontStyle::Chip8X, [0xF0, 0x80, 0xF0, 0x80, 0x80]),
+        (FontStyle::Dream6800, [0xE0, 0x80, 0xC0, 0x80, 0x80]),
+    ];
+    for (font_style, glyph) in glyphs {
+        assert!(FontStyle::all().contains(&font_style));
+        let mut chip8 = Chip8::default();
+        chip8.load_font(font_style);
+        chip8.v[0] = 0xF;
+        // i := hex v0
+        let instruction = chip8.decode(0xF029).unwrap();
+        chip8.execute(instruction).unwrap();
+        assert_eq!(chip8.i, 0x50 + 0xF * 5);
+        assert_eq!(chip8.memory_view().slice(chip8.i, 5), Some(&glyph[..]));
+    }
+}
+
+#[test]
+fn clone_without_hooks_diverges() {
+    let mut chip8 = Chip8::default();
+    chip8.extensions.push(Box::new(SoundExtension));
+    // v0 := 5, if v0 -key then v1 := 1, loop again
+    chip8.read_rom(&[0x60, 0x05, 0xE0, 0x9E, 0x61, 0x01, 0x12, 0x06]);
+    chip8.step().unwrap();
+
+    let mut clone = chip8.clone_without_hooks();
+    assert!(clone.extensions.is_empty());
+    assert_eq!(clone.pc, chip8.pc);
+    assert_eq!(clone.v, chip8.v);
+    clone.keyboard[5] = true;
+
+    for _ in 0..3 {
+        chip8.step().unwrap();
+        clone.step().unwrap();
+    }
+    assert_eq!(chip8.v[1], 1);
+    assert_eq!(clone.v[1], 0);
+    assert_eq!(chip8.pc, 0x206);
+    assert_eq!(clone.pc, 0x206);
+}
+
+#[test]
+fn random_masks() {
+    for kk in [0x00, 0xFF, 0xF0, 0x0F] {
+        let mut chip8 = Chip8::default();
+        chip8.rng.seed(0x5EED);
+        let rng = deca::fastrand::Rng::with_seed(0x5EED);
+        for _ in 0..32 {
+            // v3 := random kk
+            let instruction = chip8.decode(0xC300 | u16::from(kk)).unwrap();
+            chip8.execute(instruction).unwrap();
+            assert_eq!(chip8.v[3], rng.u8(..) & kk);
+        }
+        if kk == 0 {
+            assert_eq!(chip8.v[3], 0);
+        }
+    }
+}
+
+#[test]
+fn memory_access_wraps_around() {
+    let mut chip8 = Chip8::new(Platform::XoChip);
+    chip8.v[0] = 123;
+    chip8.i = 0xFFFF;
+    // bcd v0
+    let instruction = chip8.decode(0xF033).unwrap();
+    chip8.execute(instruction).unwrap();
+    assert_eq!(chip8[0xFFFFu16], 1);
+    assert_eq!(chip8[0x0000u16], 2);
+    assert_eq!(chip8[0x0001u16], 3);
+
+    chip8.v[..3].copy_from_slice(&[0xAA, 0xBB, 0xCC]);
+    chip8.i = 0xFFFE;
+    // save v0 - v2
+    let instruction = chip8.decode(0x5022).unwrap();
+    chip8.execute(instruction).unwrap();
+    assert_eq!(chip8[0xFFFEu16], 0xAA);
+    assert_eq!(chip8[0xFFFFu16], 0xBB);
+    assert_eq!(chip8[0x0000u16], 0xCC);
+
+    chip8.v = [0; 16];
+    // load v0 - v2
+    let instruction = chip8.decode(0x5023).unwrap();
+    chip8.execute(instruction).unwrap();
+    assert_eq!(chip8.v[..3], [0xAA, 0xBB, 0xCC]);
+
+    chip8.v = [0; 16];
+    // load v2
+    let instruction = chip8.decode(0xF265).unwrap();
+    chip8.execute(instruction).unwrap();
+    assert_eq!(chip8.v[..3], [0xAA, 0xBB, 0xCC]);
+    assert_eq!(chip8.i, 0x0001);
+
+    chip8.i = 0xFFFF;
+    // save v1
+    let instruction = chip8.decode(0xF155).unwrap();
+    chip8.execute(instruction).unwrap();
+    assert_eq!(chip8[0xFFFFu16], 0xAA);
+    assert_eq!(chip8[0x0000u16], 0xBB);
+    assert_eq!(chip8.i, 0x0001);
+}
+
+#[test]
+fn custom_start_address() {
+    let options = Options {
+        start_address: Some(0x600),
+        ..Options::default()
+    };
+    let mut chip8 = Chip8::new(options);
+    assert_eq!(chip8.pc, 0x600);
+    // v0 := 0x42
+    chip8.read_rom(&[0x60, 0x42]);
+    assert_eq!(chip8[0x600u16], 0x60);
+    assert_eq!(chip8[0x601u16], 0x42);
+    assert_eq!(chip8[0x200u16], 0x00);
+    chip8.step().unwrap();
+    assert_eq!(chip8.v[0], 0x42);
+    assert_eq!(chip8.pc, 0x602);
+
+    let options = Options {
+        start_address: Some(0x100),
+        ..Options::default()
+    };
+    let mut chip8 = Chip8::new(options);
+    chip8.read_rom(&[0x60, 0x0F, 0xF0, 0x29]);
+    chip8.run(2).unwrap();
+    assert_eq!(chip8.i, 0xFF00 + 0xF * 5);
+    assert_eq!(chip8[0x100u16], 0x60);
+}
+
+#[test]
+fn draw_overlapping_two_plane_sprites() {
+    let mut chip8 = Chip8::new(Platform::XoChip);
+    for (n, byte) in [0xFF, 0x0F, 0xF0, 0xFF].into_iter().enumerate() {
+        chip8[0x300 + n as u16] = byte;
+    }
+    // plane 3, i := 0x300, sprite v0 v1 1
+    for opcode in [0xF301, 0xA300, 0xD011] {
+        let instruction = chip8.decode(opcode).unwrap();
+        chip8.execute(instruction).unwrap();
+    }
+    assert_eq!(chip8.v[0xF], 0);
+    // i := 0x302, v0 := 2, sprite v0 v1 1
+    for opcode in [0xA302, 0x6002, 0xD011] {
+        let instruction = chip8.decode(opcode).unwrap();
+        chip8.execute(instruction).unwrap();
+    }
+    assert_eq!(chip8.v[0xF], 1);
+    let pixels: Vec<u8> = (0..10).map(|x| chip8.display.get_pixel(x, 0)).collect();
+    assert_eq!(pixels, [1, 1, 2, 2, 0, 0, 1, 1, 2, 2]);
+    assert_eq!(chip8.display.active_plane, 3);
+}
+
+#[test]
+fn step_back() {
+    let mut chip8 = Chip8::default();
+    chip8.hash_memory = true;
+    chip8.enable_reverse_step(4);
+    // v0 := 200, i := 0x300, bcd v0, sprite v0 v0 1, call 0x200
+    chip8.read_rom(&[0x60, 0xC8, 0xA3, 0x00, 0xF0, 0x33, 0xD0, 0x01, 0x22, 0x00]);
+    let original = chip8.clone_without_hooks();
+
+    chip8.step().unwrap();
+    chip8.enable_reverse_step(8);
+    for _ in 0..4 {
+        chip8.step().unwrap();
+    }
+    assert_eq!(chip8.sp, 1);
+    assert_eq!(chip8.display.active_pixel_count(), 1);
+    for _ in 0..5 {
+        assert_eq!(chip8.step_back(), Some(()));
+    }
+    assert_eq!(chip8.step_back(), None);
+    assert!(chip8 == original);
+    assert_eq!(chip8.cycles, 0);
+    assert_eq!(chip8.display.active_pixel_count(), 0);
+}
+
+#[test]
+fn step_back_full_history() {
+    let mut chip8 = Chip8::default();
+    chip8.enable_reverse_step(2);
+    // v0 += 1, sprite v0 v0 1, jump 0x200
+    chip8.read_rom(&[0x70, 0x01, 0xD0, 0x01, 0x12, 0x00]);
+    for _ in 0..3 {
+        chip8.step().unwrap();
+    }
+    let earlier = chip8.clone_without_hooks();
+    for _ in 0..2 {
+        chip8.step().unwrap();
+    }
+    assert_eq!(chip8.step_back(), Some(()));
+    assert_eq!(chip8.step_back(), Some(()));
+    assert_eq!(chip8.step_back(), None);
+    assert!(chip8 == earlier);
+    chip8.display.assert_identical_to(&earlier.display);
+}
+
+#[test]
+fn step_back_keeps_analysis_state() {
+    let mut chip8 = Chip8::default();
+    chip8.enable_coverage();
+    chip8.enable_reverse_step(4);
+    // v0 += 1, sprite v0 v0 1, jump 0x200
+    chip8.read_rom(&[0x70, 0x01, 0xD0, 0x01, 0x12, 0x00]);
+    for _ in 0..3 {
+        chip8.step().unwrap();
+    }
+    for _ in 0..3 {
+        assert_eq!(chip8.step_back(), Some(()));
+    }
+    assert_eq!(chip8.pc, 0x200);
+    assert_eq!(chip8.coverage_percentage(), Some(1.0));
+    assert_eq!(chip8.pc_high_watermark, Some(0x204));
+    assert_eq!(chip8.draw_calls, 1);
+    assert_eq!(chip8.display.total_draw_calls, 1);
+    assert_eq!(chip8.display.active_pixel_count(), 0);
+}
+
+#[test]
+fn snapshot_into_other_memory_model() {
+    let mut small = Chip8::default().with_memory_model(MemoryModel::small());
+    let mut snapshot = small.snapshot();
+    let mut chip8 = Chip8::default();
+    // v0 := 0x42, i := 0x800, save v0
+    chip8.read_rom(&[0x60, 0x42, 0xA8, 0x00, 0xF0, 0x55]);
+    chip8.run(3).unwrap();
+    chip8.snapshot_into(&mut snapshot);
+
+    small.restore(&snapshot);
+    assert_eq!(small.memory_dump().len(), 65536);
+    assert_eq!((small.pc, small.v[0], small[0x800]), (0x206, 0x42, 0x42));
+    assert!(small == chip8);
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn display_to_png() {
+    let mut chip8 = Chip8::default();
+    chip8.display.draw(vec![vec![1]], 0, 0);
+    let palette = [0x0000_00FF, 0xFF80_40FF, 0, 0];
+    let png = chip8.display_to_png_bytes(&palette, 4);
+    let image = image::load_from_memory(&png).unwrap().to_rgba8();
+    assert_eq!(image.dimensions(), (64 * 4, 32 * 4));
+    assert_eq!(image.get_pixel(0, 0).0, [0xFF, 0x80, 0x40, 0xFF]);
+    assert_eq!(image.get_pixel(3, 3).0, [0xFF, 0x80, 0x40, 0xFF]);
+    assert_eq!(image.get_pixel(4, 0).0, [0x00, 0x00, 0x00, 0xFF]);
+
+    let path = std::env::temp_dir().join("deca_display_to_png.png");
+    chip8.export_display_to_png(&path, &palette, 4).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), png);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn interrupt() {
+    let mut chip8 = Chip8::default();
+    // 0x200: jump 0x200; 0x202: v0 += 1, return
+    chip8.read_rom(&[0x12, 0x00, 0x70, 0x01, 0x00, 0xEE]);
+    chip8.interrupt_pending = Some(0x202);
+    chip8.run(3).unwrap();
+    assert_eq!(chip8.interrupt_pending, None);
+    assert_eq!(chip8.v[0], 1);
+    assert_eq!(chip8.pc, 0x200);
+    assert_eq!(chip8.sp, 0);
+
+    chip8.interrupt(0x202).unwrap();
+    assert_eq!((chip8.pc, chip8.sp, chip8.stack[0]), (0x202, 1, 0x200));
+
+    let mut chip8 = Chip8::default().with_stack_size(1);
+    chip8.interrupt(0x202).unwrap();
+    assert!(matches!(
+        chip8.interrupt(0x202),
+        Err(Chip8Error::StackOverflow { .. })
+    ));
+}
+
+#[test]
+fn font_character_out_of_range() {
+    let mut chip8 = Chip8::default();
+    chip8.v[0] = 0x10;
+    chip8.v[1] = 0xFF;
+    // i := hex v0, i := bighex v1
+    let instruction = chip8.decode(0xF029).unwrap();
+    chip8.execute(instruction).unwrap();
+    assert_eq!(chip8.i, 0x50);
+    let instruction = chip8.decode(0xF130).unwrap();
+    chip8.execute(instruction).unwrap();
+    assert_eq!(chip8.i, 0xA0 + 0xF * 10);
+
+    chip8.font_character_mask_quirk = Some(false);
+    chip8.i = 0;
+    for opcode in [0xF029, 0xF130] {
+        let instruction = chip8.decode(opcode).unwrap();
+        assert!(matches!(
+            chip8.execute(instruction),
+            Err(Chip8Error::InvalidFontCharacter(0x10 | 0xFF))
+        ));
+    }
+    assert_eq!(chip8.i, 0);
+    chip8.v[0] = 0xF;
+    let instruction = chip8.decode(0xF029).unwrap();
+    chip8.execute(instruction).unwrap();
+    assert_eq!(chip8.i, 0x50 + 0xF * 5);
+}
+
+#[test]
+fn store_and_load_wrap_index() {
+    for load_store in [false, true] {
+        let mut chip8 = Chip8::default();
+        chip8.set_load_store_quirk(load_store);
+        chip8.v[..3].copy_from_slice(&[1, 2, 3]);
+        chip8.i = 0xFFFE;
+        // save v2
+        let instruction = chip8.decode(0xF255).unwrap();
+        chip8.execute(instruction).unwrap();
+        assert_eq!(chip8[0xFFFEu16], 1);
+        assert_eq!(chip8[0xFFFFu16], 2);
+        assert_eq!(chip8[0x0000u16], 3);
+        assert_eq!(chip8.i, if load_store { 0xFFFE } else { 0x0001 });
+
+        chip8.v = [0; 16];
+        chip8.i = 0xFFFE;
+        // load v2
+        let instruction = chip8.decode(0xF265).unwrap();
+        chip8.execute(instruction).unwrap();
+        assert_eq!(chip8.v[..3], [1, 2, 3]);
+        assert_eq!(chip8.i, if load_store { 0xFFFE } else { 0x0001 });
+
+        // saveflags v2, loadflags v2
+        chip8.v = [4; 16];
+        let instruction = chip8.decode(0xF275).unwrap();
+        chip8.execute(instruction).unwrap();
+        chip8.v = [0; 16];
+        let instruction = chip8.decode(0xF285).unwrap();
+        chip8.execute(instruction).unwrap();
+        assert_eq!(chip8.v[..4], [4, 4, 4, 0]);
+        assert_eq!(chip8.i, if load_store { 0xFFFE } else { 0x0001 });
+    }
+}
+
+#[test]
+fn timer_callback() {
+    use std::sync::{Arc, Mutex};
+    let mut chip8 = Chip8::default();
+    let ticks = Arc::new(Mutex::new(Vec::new()));
+    let callback_ticks = Arc::clone(&ticks);
+    chip8.set_timer_callback(move |delay, sound| {
+        callback_ticks.lock().unwrap().push((delay, sound))
+    });
+    chip8.tick_timers();
+    assert!(ticks.lock().unwrap().is_empty());
+
+    chip8.sound = 2;
+    chip8.tick_timers();
+    chip8.tick_timers();
+    chip8.tick_timers();
+    assert_eq!(*ticks.lock().unwrap(), [(0, 1), (0, 0)]);
+
+    // The delay timer always decrements with the delay_wrap quirk
+    chip8.options.quirks.delay_wrap = Some(true);
+    chip8.tick_timers();
+    assert_eq!(ticks.lock().unwrap().last(), Some(&(255, 0)));
+}
+
+#[test]
+fn draw_with_plane_0() {
+    let mut chip8 = Chip8::new(Platform::XoChip);
+    chip8[0x300u16] = 0xFF;
+    // plane 0, i := 0x300, sprite v0 v1 1
+    for opcode in [0xF001, 0xA300, 0xD011] {
+        let instruction = chip8.decode(opcode).unwrap();
+        chip8.execute(instruction).unwrap();
+    }
+    assert_eq!(chip8.display.active_plane, 0);
+    assert_eq!(chip8.display.active_pixel_count(), 0);
+    assert!(chip8.display.clear);
+    assert_eq!(chip8.v[0xF], 0);
+    // Drawing the same sprite again can't collide either
+    let instruction = chip8.decode(0xD011).unwrap();
+    chip8.execute(instruction).unwrap();
+    assert_eq!(chip8.v[0xF], 0);
+}
+
+#[test]
+fn memory_dump() {
+    let mut chip8 = Chip8::default();
+    chip8.memory_dump_mut()[0x300] = 0x42;
+    assert_eq!(chip8[0x300u16], 0x42);
+    assert_eq!(chip8.memory_dump()[0x300], 0x42);
+    assert_eq!(chip8.memory_dump().len(), 65536);
+    assert_eq!(chip8.memory_region_slice(0x300, 0x301), Some(&[0x42][..]));
+    assert_eq!(chip8.memory_region_slice(0x300, 0x300), Some(&[][..]));
+    assert_eq!(chip8.memory_region_slice(0x301, 0x300), None);
+    assert_eq!(
+        chip8.memory_region_slice(0, 65536).map(<[u8]>::len),
+        Some(65536)
+    );
+}
+
+#[test]
+fn sound_timer_counts_down_to_zero() {
+    let mut chip8 = Chip8::default();
+    // 0x200: jump 0x200
+    chip8.read_rom(&[0x12, 0x00]);
+    chip8.run(1).unwrap();
+    assert_eq!(chip8.sound, 0);
+
+    chip8.sound = 2;
+    chip8.run(1).unwrap();
+    assert_eq!(chip8.sound, 1);
+    assert!(chip8.sound_active());
+    chip8.run(1).unwrap();
+    assert_eq!(chip8.sound, 0);
+    assert!(!chip8.sound_active());
+    chip8.run(1).unwrap();
+    assert_eq!(chip8.sound, 0);
+}
+
+#[test]
+fn state_diff() {
+    let mut a = Chip8::default();
+    // v0 := 1, i := 0x202, save v0
+    a.read_rom(&[0x60, 0x01, 0xA2, 0x02, 0xF0, 0x55]);
+    let b = a.clone_without_hooks();
+    a.assert_no_diff(&b);
+    assert!(a.state_diff(&b).is_empty());
+
+    a.run(3).unwrap();
+    a.display.draw(vec![vec![1]], 2, 3);
+    let diff = a.state_diff(&b);
+    assert!(diff.pc_differs && diff.i_differs);
+    assert!(!diff.timers_differ);
+    assert!(!diff.stack_differs);
+    assert_eq!(diff.registers, [(0, 1, 0)]);
+    assert_eq!(diff.memory_diffs, [(0x202, 0x01, 0xA2)]);
+    assert_eq!(diff.display_diffs, [(2, 3, 1, 0)]);
+    assert!(diff.to_string().contains("V0: 0x01 != 0x00"));
+}
+
+#[test]
+#[should_panic(expected = "0x0202: 0x01 != 0xA2")]
+fn assert_no_diff() {
+    let mut a = Chip8::default();
+    a.read_rom(&[0x00, 0xE0, 0xA2]);
+    let b = a.clone_without_hooks();
+    a[0x202u16] = 0x01;
+    a.assert_no_diff(&b);
+}
+
+#[test]
+fn shift_with_vf_operands() {
+    // (shift quirk, opcode low nibble, expected VX if it isn't VF, expected VF)
+    let cases = [
+        (false, 0x6, 0x02, 1),
+        (false, 0xE, 0x0A, 0),
+        (true, 0x6, 0x41, 1),
+        (true, 0xE, 0x06, 1),
+    ];
+    for (shift, nibble, result, flag) in cases {
+        for (x, y) in [(1, 2), (1, 0xF), (0xF, 2)] {
+            let mut chip8 = Chip8::default();
+            chip8.set_shift_quirk(shift);
+            chip8.v[x] = 0x83;
+            if y != x {
+                chip8.v[y] = 0x05;
+            }
+            let opcode = 0x8000 | (x as u16) << 8 | (y as u16) << 4 | nibble;
+            let instruction = chip8.decode(opcode).unwrap();
+            chip8.execute(instruction).unwrap();
+            if x != 0xF {
+                assert_eq!(chip8.v[x], result, "{opcode:04X}, shift quirk {shift}");
+            }
+            assert_eq!(chip8.v[0xF], flag, "{opcode:04X}, shift quirk {shift}");
+        }
+    }
+
+    // Even with VF written first, the original VF is shifted
+    let mut chip8 = Chip8::default();
+    chip8.vf_conflict_quirk = Some(false);
+    chip8.v[0xF] = 0x05;
+    let instruction = chip8.decode(0x81F6).unwrap();
+    chip8.execute(instruction).unwrap();
+    assert_eq!((chip8.v[1], chip8.v[0xF]), (0x02, 1));
+}
+
+#[test]
+fn run_until_display_draw() {
+    let mut chip8 = Chip8::default();
+    // 0x200: v0 += 1; 0x202: if v0 != 3 then jump 0x200; 0x206: sprite v0 v0 1; 0x208: jump 0x208
+    chip8.read_rom(&[0x70, 0x01, 0x30, 0x03, 0x12, 0x00, 0xD0, 0x01, 0x12, 0x08]);
+    chip8.i = 0x50;
+    chip8.display.dirty = true;
+    assert_eq!(
+        chip8.run_until_display_draw(4).unwrap(),
+        RunResult {
+            instructions_executed: 4,
+            stop_reason: StopReason::TickLimit,
+        }
+    );
+    assert!(!chip8.display.dirty);
+    assert_eq!(
+        chip8.run_until_display_draw(100).unwrap(),
+        RunResult {
+            instructions_executed: 5,
+            stop_reason: StopReason::DisplayDrawn,
+        }
+    );
+    assert_eq!(chip8.pc, 0x208);
+    assert_eq!(chip8.display.frame_number, 0);
+}
+
+#[test]
+fn store_all_registers_wraps() {
+    for load_store in [false, true] {
+        let mut chip8 = Chip8::default();
+        chip8.set_load_store_quirk(load_store);
+        for n in 0..16 {
+            chip8.v[n] = n as u8 + 1;
+        }
+        chip8.i = 0xFFF1;
+        // save vF
+        let instruction = chip8.decode(0xFF55).unwrap();
+        chip8.execute(instruction).unwrap();
+        for n in 0..15 {
+            assert_eq!(chip8[0xFFF1 + n], n as u8 + 1);
+        }
+        assert_eq!(chip8[0x0000u16], 16);
+        assert_eq!(chip8.i, if load_store { 0xFFF1 } else { 0x0001 });
+    }
+}
+
+#[test]
+fn store_and_load_reversed_range() {
+    let mut chip8 = Chip8::new(Platform::XoChip);
+    chip8.v[1..4].copy_from_slice(&[0x11, 0x22, 0x33]);
+    chip8.i = 0x300;
+    // save v3 - v1
+    let instruction = chip8.decode(0x5312).unwrap();
+    chip8.execute(instruction).unwrap();
+    assert_eq!(
+        chip8.memory_region_slice(0x300, 0x304),
+        Some(&[0x33, 0x22, 0x11, 0][..])
+    );
+    assert_eq!(chip8.i, 0x300);
+
+    chip8[0x300u16] = 0xAA;
+    chip8[0x302u16] = 0xCC;
+    // load v3 - v1
+    let instruction = chip8.decode(0x5313).unwrap();
+    chip8.execute(instruction).unwrap();
+    assert_eq!(chip8.v[..5], [0, 0xCC, 0x22, 0xAA, 0]);
+    assert_eq!(chip8.i, 0x300);
+}
+
+#[test]
+fn dump_registers_and_stack() {
+    let mut chip8 = Chip8::default();
+    chip8.v[1] = 0xFF;
+    chip8.v[0xF] = 0x01;
+    chip8.i = 0x300;
+    chip8.delay = 48;
+    assert_eq!(chip8.dump_stack(), "");
+    // call 0x208, call 0x350
+    chip8.read_rom(&[0x22, 0x08, 0, 0, 0, 0, 0, 0, 0x23, 0x50]);
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+    assert_eq!(
+        chip8.dump_registers(),
+        "V0=00 V1=FF V2=00 V3=00 V4=00 V5=00 V6=00 V7=00 V8=00 V9=00 VA=00 VB=00 VC=00 VD=00 \
+         VE=00 VF=01 I=0300 PC=0350 SP=2 DLY=48 SND=0"
+    );
+    assert_eq!(chip8.dump_stack(), "STACK[1]=0202 STACK[2]=020A");
+}
+
+#[test]
+fn draw_empty_sprite() {
+    let mut chip8 = Chip8::default();
+    chip8.i = 0x300;
+    // sprite v0 v0 1
+    let instruction = chip8.decode(0xD001).unwrap();
+    chip8.execute(instruction).unwrap();
+    assert!(!chip8.display.dirty);
+    assert!(chip8.display.clear);
+    assert_eq!(chip8.draw_calls, 1);
+
+    chip8
+        .display
+        .draw_raw_sprite(0, 0, &[0, 0], 16, deca::DrawFlags::default());
+    assert!(!chip8.display.dirty);
+    assert!(chip8.display.clear);
+    chip8.display.draw(vec![vec![1]], 0, 0);
+    assert!(chip8.display.dirty);
+    assert!(!chip8.display.clear);
+}
+
+#[test]
+fn memory_statistics() {
+    let mut chip8 = Chip8::default();
+    chip8.memory_dump_mut().fill(0);
+    let stats = chip8.memory_statistics();
+    assert_eq!(stats.zero_bytes, 65536);
+    assert_eq!(stats.total_nonzero, 0);
+    assert_eq!(stats.entropy, 0.0);
+
+    // Half of memory is 0x00 and half 0xFF, which is one bit of entropy
+    chip8.memory_dump_mut()[..32768].fill(0xFF);
+    chip8.read_rom(&[0xFF; 4]);
+    let stats = chip8.memory_statistics();
+    assert_eq!(stats.font_region, (0x50, 0x140));
+    assert_eq!(stats.rom_region, (0x200, 0x204));
+    assert_eq!((stats.zero_bytes, stats.total_nonzero), (32768, 32768));
+    assert!((stats.entropy - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn run_zero_ticks() {
+    let mut chip8 = Chip8::default();
+    chip8.read_rom(&[0x60, 0x01]);
+    chip8.delay = 5;
+    chip8.sound = 3;
+    let result = chip8.run(0).unwrap();
+    assert_eq!(result.instructions_executed, 0);
+    assert_eq!(chip8.pc, 0x200);
+    assert_eq!(chip8.v[0], 0);
+    assert_eq!((chip8.delay, chip8.sound), (5, 3));
+    assert_eq!(chip8.display.frame_number, 0);
+}
+
+#[test]
+fn execute_raw_arithmetic() {
+    // Each case is (opcode, VX, VY, expected VX, expected VF), with X = 1 and Y = 2. VF starts
+    // out as 0xAA, so instructions that don't set a flag leave it as that
+    let cases: [(u16, u8, u8, u8, u8); 16] = [
+        (0x7105, 0xFE, 0, 0x03, 0xAA),
+        (0x8120, 0x01, 0x02, 0x02, 0xAA),
+        (0x8121, 0x0C, 0x0A, 0x0E, 0xAA),
+        (0x8122, 0x0C, 0x0A, 0x08, 0xAA),
+        (0x8123, 0x0C, 0x0A, 0x06, 0xAA),
+        (0x8124, 0x10, 0x20, 0x30, 0),
+        (0x8124, 0xF0, 0x20, 0x10, 1),
+        (0x8125, 0x20, 0x10, 0x10, 1),
+        (0x8125, 0x10, 0x10, 0x00, 1),
+        (0x8125, 0x10, 0x20, 0xF0, 0),
+        (0x8127, 0x10, 0x20, 0x10, 1),
+        (0x8127, 0x20, 0x10, 0xF0, 0),
+        (0x8126, 0x00, 0x05, 0x02, 1),
+        (0x8126, 0x00, 0x04, 0x02, 0),
+        (0x812E, 0x00, 0x81, 0x02, 1),
+        (0x812E, 0x00, 0x41, 0x82, 0),
+    ];
+    for (opcode, vx, vy, expected_vx, expected_vf) in cases {
+        let mut chip8 = Chip8::default();
+        chip8.v[1] = vx;
+        chip8.v[2] = vy;
+        chip8.v[0xF] = 0xAA;
+        chip8.execute_raw(opcode).unwrap();
+        assert_eq!(chip8.v[1], expected_vx, "VX after {opcode:04X}");
+        assert_eq!(chip8.v[2], vy, "VY after {opcode:04X}");
+        assert_eq!(chip8.v[0xF], expected_vf, "VF after {opcode:04X}");
+    }
+}
+
+#[test]
+fn execute_raw_unknown_opcode() {
+    let mut chip8 = Chip8::default();
+    assert!(matches!(
+        chip8.execute_raw(0xFFFF),
+        Err(Chip8Error::Decode(DecodeError::UnknownOpcode {
+            opcode: 0xFFFF,
+            pc: 0x200,
+        }))
+    ));
+    assert_eq!(chip8.pc, 0x200);
+    let error = chip8.execute_raw(0xFFFF).unwrap_err();
+    let source = std::error::Error::source(&error).unwrap();
+    assert!(source.downcast_ref::<DecodeError>().is_some());
+
+    // Errors report where the most recent opcode was fetched from
+    // 0x200: v0 := 1; 0x202: unknown
+    chip8.read_rom(&[0x60, 0x01, 0xFF, 0xFF]);
+    chip8.step().unwrap();
+    assert!(matches!(
+        chip8.step(),
+        Err(Chip8Error::Decode(DecodeError::UnknownOpcode {
+            pc: 0x202,
+            ..
+        }))
+    ));
+}
+
+#[test]
+fn vblank_counts_the_draw() {
+    let mut chip8 = Chip8::default();
+    chip8.set_vblank_quirk(true);
+    chip8.i = 0x50;
+    // 0x200: sprite v0 v0 5; 0x202: v1 := 1
+    chip8.read_rom(&[0xD0, 0x05, 0x61, 0x01]);
+    let result = chip8.run(10).unwrap();
+    assert_eq!(result.instructions_executed, 1);
+    assert_eq!(result.stop_reason, StopReason::VBlank);
+    assert_eq!(chip8.draw_calls, 1);
+    assert!(chip8.display.active_pixel_count() > 0);
+    assert_eq!(chip8.v[1], 0);
+    assert_eq!(chip8.pc, 0x202);
+}
+
+#[test]
+fn random_memory() {
+    let chip8 = Chip8::default();
+    assert!(chip8.memory_dump()[..0x50].iter().all(|&byte| byte == 0));
+    assert!(chip8.memory_dump()[0x140..].iter().all(|&byte| byte == 0));
+
+    let chip8 = Chip8::default();
+    chip8.rng.seed(1);
+    let chip8 = chip8.with_random_memory();
+    assert!(chip8.memory_dump()[0x200..].iter().any(|&byte| byte != 0));
+    assert_eq!(
+        chip8.memory_dump()[0x50..0x140],
+        Chip8::default().memory_dump()[0x50..0x140]
+    );
+}
+
+#[test]
+fn peek() {
+    let mut chip8 = Chip8::default();
+    // 0x200: v0 := 0x12; 0x202: i := long 0x0345
+    chip8.read_rom(&[0x60, 0x12, 0xF0, 0x00, 0x03, 0x45]);
+    assert_eq!(chip8.peek(), 0x6012);
+    assert_eq!(chip8.peek(), 0x6012);
+    assert_eq!(chip8.pc, 0x200);
+    assert_eq!(chip8.peek(), chip8.fetch());
+
+    let (opcode, instruction) = chip8.peek_instruction();
+    assert_eq!(opcode, 0xF000);
+    assert!(matches!(instruction, Ok(Instruction::SetIndex(0x0345))));
+    assert_eq!(chip8.pc, 0x202);
+    assert!(matches!(chip8.step(), Ok(Instruction::SetIndex(0x0345))));
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn arbitrary_chip8() {
+    use arbitrary::{Arbitrary, Unstructured};
+    let rng = fastrand::Rng::with_seed(1);
+    for _ in 0..1000 {
+        let data: Vec<u8> = (0..512).map(|_| rng.u8(..)).collect();
+        let mut chip8 = Chip8::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert!(chip8.pc >= 0x200);
+        assert!(chip8.sp <= chip8.stack.len());
+        let (_, instruction) = chip8.peek_instruction();
+        assert!(instruction.is_ok());
+        // The instruction at the Program Counter always decodes, so any error is a runtime error
+        assert!(!matches!(chip8.step(), Err(Chip8Error::Decode(_))));
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn execute_arbitrary_instructions() {
+    use arbitrary::{Arbitrary, Unstructured};
+    let rng = fastrand::Rng::with_seed(2);
+    for _ in 0..100 {
+        let data: Vec<u8> = (0..4096).map(|_| rng.u8(..)).collect();
+        let mut u = Unstructured::new(&data);
+        let mut chip8 = Chip8::arbitrary(&mut u).unwrap();
+        for _ in 0..1000 {
+            let instruction = deca::arbitrary_instruction(&mut u).unwrap();
+            // Errors are fine, as long as nothing panics
+            let _ = chip8.execute(instruction);
+        }
+    }
+}
+
+#[test]
+fn execute_every_opcode() {
+    let rng = fastrand::Rng::with_seed(3);
+    let mut chip8 = Chip8::default();
+    for opcode in 0..=u16::MAX {
+        for register in chip8.v.iter_mut() {
+            *register = rng.u8(..);
+        }
+        chip8.i = rng.u16(..);
+        // Errors are fine, as long as nothing panics
+        let _ = chip8.execute_raw(opcode);
+    }
+}
+
+#[test]
+fn skip_key_uses_lowest_nibble() {
+    let mut chip8 = Chip8::default();
+    chip8.keyboard[0x3] = true;
+    chip8.v[0] = 0xF3;
+    // 0x200: if v0 -key then; 0x204: if v0 key then
+    chip8.read_rom(&[0xE0, 0x9E, 0x00, 0x00, 0xE0, 0xA1]);
+    chip8.step().unwrap();
+    assert_eq!(chip8.pc, 0x204);
+    chip8.step().unwrap();
+    assert_eq!(chip8.pc, 0x206);
+}
+
+#[test]
+fn call_return_address() {
+    let mut chip8 = Chip8::default();
+    let mut rom = vec![0; 0x202];
+    // 0x200: call 0x300
+    rom[..2].copy_from_slice(&[0x23, 0x00]);
+    // 0x300: call 0x400; 0x302: return
+    rom[0x100..0x104].copy_from_slice(&[0x24, 0x00, 0x00, 0xEE]);
+    // 0x400: return
+    rom[0x200..].copy_from_slice(&[0x00, 0xEE]);
+    chip8.read_rom(&rom);
+
+    chip8.step().unwrap();
+    assert_eq!(chip8.pc, 0x300);
+    assert_eq!(chip8.stack[..chip8.sp], [0x202]);
+    chip8.step().unwrap();
+    assert_eq!(chip8.pc, 0x400);
+    assert_eq!(chip8.stack[..chip8.sp], [0x202, 0x302]);
+    chip8.step().unwrap();
+    assert_eq!(chip8.pc, 0x302);
+    assert_eq!(chip8.sp, 1);
+    chip8.step().unwrap();
+    assert_eq!(chip8.pc, 0x202);
+    assert_eq!(chip8.sp, 0);
+}
+
+#[test]
+fn timer_accessors() {
+    let mut chip8 = Chip8::default();
+    chip8.set_delay_timer(3);
+    chip8.set_sound_timer(2);
+    assert_eq!((chip8.delay_timer(), chip8.sound_timer()), (3, 2));
+    assert!(chip8.sound_active());
+
+    let mut events = Vec::new();
+    chip8.tick_timers_with_callback(|event| events.push(event));
+    assert_eq!(events, [TimerEvent::SoundStarted]);
+    assert_eq!((chip8.delay_timer(), chip8.sound_timer()), (2, 1));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn disassemble_to_writer() {
+    let mut chip8 = Chip8::default();
+    chip8.read_rom(&[
+        0x00, 0xE0, // cls
+        0x60, 0x12, // v0 := 0x12
+        0xF0, 0x00, 0x03, 0x45, // i := long 0x0345
+        0x80, 0x16, // v0 >>= v1
+        0xFF, 0xFF, // unknown
+        0xD0, 0x15, // sprite v0 v1 5
+    ]);
+    let mut output = Vec::new();
+    chip8
+        .disassemble_to_writer(0x200, 0x20E, &mut output)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "0200: 00E0  CLS\n\
+         0202: 6012  LD\n\
+         0204: F000 0345  LD\n\
+         0208: 8016  SHR\n\
+         020A: FFFF  ???? (0xFFFF)\n\
+         020C: D015  DRW\n"
+    );
+}
+
+#[test]
+fn small_memory() {
+    let mut chip8 = Chip8::default().with_memory_model(MemoryModel::small());
+    assert_eq!(chip8.memory_dump().len(), 4096);
+    assert!(matches!(
+        chip8.warm_load(&[0; 3585]),
+        Err(Chip8Error::RomTooLarge {
+            size: 3585,
+            max_size: 3584
+        })
+    ));
+
+    let mut rom = vec![0; 3584];
+    // 0x200: v0 := 123; 0x202: i := 0xFFE; 0x204: bcd v0; 0x206: i := hex v0; 0x208: jump 0xFFC
+    rom[..10].copy_from_slice(&[0x60, 0x7B, 0xAF, 0xFE, 0xF0, 0x33, 0xF0, 0x29, 0x1F, 0xFC]);
+    // 0xFFC: v1 := 1
+    rom[0xDFC..0xDFE].copy_from_slice(&[0x61, 0x01]);
+    chip8.warm_load(&rom).unwrap();
+    for _ in 0..6 {
+        chip8.step().unwrap();
+    }
+    // The BCD wraps around to the start of memory
+    assert_eq!((chip8[0xFFE], chip8[0xFFF], chip8[0x000]), (1, 2, 3));
+    assert_eq!(chip8.i, 0x50 + 0xB * 5);
+    assert_eq!(chip8.v[1], 1);
+    assert_eq!(chip8.pc, 0xFFE);
+    // Addresses past 4 KB wrap around
+    assert_eq!(chip8[0x1050], chip8[0x50]);
+}
+
+#[test]
+fn small_memory_bounds() {
+    let mut chip8 = Chip8::default().with_memory_model(MemoryModel::small());
+    assert_eq!(chip8.read_rom(&[0x12; 5000]), 3584);
+    assert_eq!(chip8.rom_length, 3584);
+    assert_eq!(chip8.memory_statistics().rom_region, (0x200, 0x1000));
+
+    // A program that starts too low for the font at 0x50 has it at the end of the 4 KB instead
+    let options = Options {
+        start_address: Some(0x100),
+        ..Options::default()
+    };
+    let mut chip8 = Chip8::new(options).with_memory_model(MemoryModel::small());
+    assert_eq!(chip8.memory_statistics().font_region, (0xF00, 0xFF0));
+    assert_eq!(chip8[0xF00], Chip8::default()[0x50]);
+    chip8.v[0] = 1;
+    chip8.execute_raw(0xF029).unwrap();
+    assert_eq!(chip8.i, 0xF05);
+}
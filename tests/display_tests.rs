@@ -0,0 +1,526 @@
+use deca::{Chip8Error, Display, DrawFlags};
+
+#[test]
+fn active_pixel_count() {
+    let mut display = Display::new();
+    assert_eq!(display.active_pixel_count(), 0);
+
+    display.draw(vec![vec![1, 0, 1, 1], vec![0, 1]], 0, 0);
+    assert_eq!(display.active_pixel_count(), 4);
+    assert_eq!(display.active_pixel_count_plane(1), 4);
+    assert_eq!(display.active_pixel_count_plane(2), 0);
+
+    display.clear_all_planes();
+    assert_eq!(display.active_pixel_count(), 0);
+
+    display.display = vec![1; 128 * 64];
+    assert_eq!(display.active_pixel_count(), 64 * 32);
+    display.hires(false);
+    assert_eq!(display.active_pixel_count(), 128 * 64);
+}
+
+#[test]
+fn compare_displays() {
+    let mut a = Display::new();
+    let mut b = Display::new();
+    assert!(a.compare(&b).is_empty());
+    assert!(a.is_identical_to(&b));
+    a.assert_identical_to(&b);
+
+    a.draw(vec![vec![1, 1]], 3, 4);
+    b.draw(vec![vec![1]], 3, 4);
+    assert_eq!(a.compare(&b), vec![(4, 4, 1, 0)]);
+    assert!(!a.is_identical_to(&b));
+
+    b.draw(vec![vec![0, 1]], 3, 4);
+    assert!(a.is_identical_to(&b));
+
+    // Pixels outside the current resolution aren't compared
+    a.display[40 * 128 + 100] = 3;
+    assert!(a.is_identical_to(&b));
+    a.hires(false);
+    b.hires(false);
+    assert_eq!(a.compare(&b), vec![(100, 40, 3, 0)]);
+}
+
+#[test]
+fn compare_displays_of_different_sizes() {
+    let mut a = Display::new();
+    let mut b = Display::new();
+    b.resize(256, 192).unwrap();
+    a.draw(vec![vec![1]], 1, 1);
+    b.draw(vec![vec![1]], 1, 1);
+    b.draw(vec![vec![1]], 200, 100);
+    assert_eq!(a.compare(&b), vec![(200, 100, 0, 1)]);
+    assert_eq!(b.compare(&a), vec![(200, 100, 1, 0)]);
+    assert!(!a.is_identical_to(&b));
+
+    // Resizing back reallocates the buffer, which then compares like any other display
+    b.resize(64, 32).unwrap();
+    b.draw(vec![vec![1]], 1, 1);
+    assert!(a.compare(&b).is_empty());
+    assert!(a.is_identical_to(&b));
+}
+
+#[test]
+#[should_panic(expected = "(4, 4): 1 != 0")]
+fn assert_identical_displays() {
+    let mut a = Display::new();
+    a.draw(vec![vec![1, 1]], 3, 4);
+    let mut b = Display::new();
+    b.draw(vec![vec![1]], 3, 4);
+    a.assert_identical_to(&b);
+}
+
+#[test]
+fn resize_display() {
+    let mut display = Display::new();
+    display.resize(256, 192).unwrap();
+    assert_eq!(
+        (display.width, display.height, display.stride),
+        (256, 192, 256)
+    );
+    assert_eq!(display.display.len(), 256 * 192);
+
+    display.draw(vec![vec![1, 1]], 254, 191);
+    assert_eq!(display.get_pixel(254, 191), 1);
+    assert_eq!(display.get_pixel(255, 191), 1);
+    assert_eq!(display.active_pixel_count(), 2);
+
+    display.scroll_left(4);
+    assert_eq!(display.get_pixel(250, 191), 1);
+    assert_eq!(display.get_pixel(251, 191), 1);
+    assert_eq!(display.active_pixel_count(), 2);
+
+    // The buffer never shrinks below what high-resolution mode needs
+    display.resize(64, 32).unwrap();
+    assert_eq!(display.display.len(), 128 * 64);
+    assert_eq!(display.active_pixel_count(), 0);
+
+    assert!(display.resize(0, 32).is_err());
+    assert!(display.resize(512, 32).is_err());
+}
+
+#[test]
+fn draw_raw_sprite() {
+    let mut display = Display::new();
+    let sprite = [0b1000_0001, 0b0000_0001, 0b1100_0000, 0b0000_0000];
+    assert_eq!(
+        display.draw_raw_sprite(0, 0, &sprite, 16, DrawFlags::default()),
+        0
+    );
+    assert_eq!(display.get_pixel(0, 0), 1);
+    assert_eq!(display.get_pixel(7, 0), 1);
+    assert_eq!(display.get_pixel(15, 0), 1);
+    assert_eq!(display.get_pixel(0, 1), 1);
+    assert_eq!(display.get_pixel(1, 1), 1);
+    assert_eq!(display.active_pixel_count(), 5);
+
+    assert_eq!(
+        display.draw_raw_sprite(0, 1, &[0b1000_0000], 8, DrawFlags::default()),
+        1
+    );
+    assert_eq!(display.get_pixel(0, 1), 0);
+
+    // Clipped at the edges by default
+    let mut display = Display::new();
+    display.draw_raw_sprite(60, 31, &[0xFF, 0xFF], 8, DrawFlags::default());
+    assert_eq!(display.active_pixel_count(), 4);
+
+    let mut display = Display::new();
+    display.draw_raw_sprite(60, 31, &[0xFF, 0xFF], 8, DrawFlags { wrap: true });
+    assert_eq!(display.active_pixel_count(), 16);
+    assert_eq!(display.get_pixel(3, 0), 1);
+    assert_eq!(display.get_pixel(4, 0), 0);
+}
+
+#[test]
+fn draw_with_row_info() {
+    let mut display = Display::new();
+    let sprite = vec![vec![1, 0], vec![0, 1], vec![1, 1]];
+    let mut row_collisions = 0;
+    assert_eq!(
+        display.draw_with_row_info(sprite, 0, 0, Some(&mut row_collisions)),
+        0
+    );
+    assert_eq!(row_collisions, 0);
+
+    let sprite = vec![vec![0, 1], vec![0, 0], vec![1, 0]];
+    assert_eq!(
+        display.draw_with_row_info(sprite, 0, 1, Some(&mut row_collisions)),
+        1
+    );
+    assert_eq!(row_collisions, 0b001);
+    assert_eq!(display.draw(vec![vec![1]], 0, 2), 1);
+}
+
+fn scrolled_pixels(hires: bool, scroll: Scroll, pixels: u8) -> Vec<(u8, u8)> {
+    let mut display = Display::new();
+    if hires {
+        display.hires(false);
+    }
+    display.draw(vec![vec![1, 0, 1]], 20, 5);
+    scroll(&mut display, pixels);
+    let mut lit = Vec::new();
+    for y in 0..display.height as u8 {
+        for x in 0..display.width as u8 {
+            if display.get_pixel(x, y) != 0 {
+                lit.push((x, y));
+            }
+        }
+    }
+    lit
+}
+
+#[test]
+fn scroll_left_and_right() {
+    for hires in [false, true] {
+        for pixels in [1, 4, 8] {
+            assert_eq!(
+                scrolled_pixels(hires, Display::scroll_left, pixels),
+                [(20 - pixels, 5), (22 - pixels, 5)]
+            );
+            assert_eq!(
+                scrolled_pixels(hires, Display::scroll_right, pixels),
+                [(20 + pixels, 5), (22 + pixels, 5)]
+            );
+        }
+    }
+}
+
+#[test]
+fn scroll_up_and_down() {
+    for hires in [false, true] {
+        for pixels in [1, 2, 4] {
+            assert_eq!(
+                scrolled_pixels(hires, Display::scroll_up, pixels),
+                [(20, 5 - pixels), (22, 5 - pixels)]
+            );
+            assert_eq!(
+                scrolled_pixels(hires, Display::scroll_down, pixels),
+                [(20, 5 + pixels), (22, 5 + pixels)]
+            );
+        }
+
+        // The rows scrolled in from the edge are cleared
+        let mut display = Display::new();
+        if hires {
+            display.hires(false);
+        }
+        let (width, height) = (display.width as u8, display.height as u8);
+        display.draw(vec![vec![1]; 2], width - 1, height - 2);
+        display.scroll_up(2);
+        assert_eq!(display.active_pixel_count(), 2);
+        assert_eq!(display.get_pixel(width - 1, height - 4), 1);
+        assert_eq!(display.get_pixel(width - 1, height - 3), 1);
+        display.clear_all_planes();
+        display.draw(vec![vec![1]; 2], 0, 0);
+        display.scroll_down(2);
+        assert_eq!(display.active_pixel_count(), 2);
+        assert_eq!(display.get_pixel(0, 2), 1);
+        assert_eq!(display.get_pixel(0, 3), 1);
+    }
+}
+
+#[test]
+fn to_xbm() {
+    let mut display = Display::new();
+    display.resize(10, 2).unwrap();
+    display.draw(vec![vec![1, 1, 0, 0, 0, 0, 0, 0, 1]], 0, 0);
+    display.draw(vec![vec![1]], 9, 1);
+    assert_eq!(
+        display.to_xbm("test"),
+        "#define test_width 10
+#define test_height 2
+static unsigned char test_bits[] = {
+   0x03, 0x01, 0x00, 0x02 };
+"
+    );
+}
+
+#[test]
+fn select_plane() {
+    let mut display = Display::new();
+    display.plane(2).unwrap();
+    assert_eq!(display.active_plane, 2);
+    display.plane(0).unwrap();
+    assert_eq!(display.active_plane, 0);
+    display.plane(3).unwrap();
+    assert_eq!(display.active_plane, 3);
+    let error = display.plane(4).unwrap_err();
+    assert!(matches!(error, Chip8Error::InvalidPlane(4)));
+    assert_eq!(
+        error.to_string(),
+        "Plane bitmask must be 0-3 (supporting up to 2 bitplanes), got 4"
+    );
+    assert_eq!(display.active_plane, 3);
+}
+
+#[test]
+fn clear_planes() {
+    let mut display = Display::new();
+    display.plane(3).unwrap();
+    display.draw(vec![vec![1]], 0, 0);
+    display.plane(1).unwrap();
+    display.clear_active_plane();
+    assert_eq!(display.get_pixel(0, 0), 2);
+    display.clear_all_planes();
+    assert_eq!(display.get_pixel(0, 0), 0);
+}
+
+type Scroll = fn(&mut Display, u8);
+
+#[test]
+fn scroll_boundaries() {
+    for hires in [false, true] {
+        let mut display = Display::new();
+        if hires {
+            display.hires(false);
+        }
+        let (width, height) = (display.width as u8, display.height as u8);
+        let scrolls: [(Scroll, u8); 4] = [
+            (Display::scroll_up, height),
+            (Display::scroll_down, height),
+            (Display::scroll_left, width),
+            (Display::scroll_right, width),
+        ];
+        for (scroll, size) in scrolls {
+            let draw = |display: &mut Display| {
+                display.draw(vec![vec![1]], 0, 0);
+                display.draw(vec![vec![1]], width - 1, height - 1);
+            };
+            for pixels in [size, size.saturating_add(1), u8::MAX] {
+                draw(&mut display);
+                scroll(&mut display, pixels);
+                assert_eq!(display.active_pixel_count(), 0);
+            }
+            draw(&mut display);
+            scroll(&mut display, 0);
+            assert_eq!(display.active_pixel_count(), 2);
+            scroll(&mut display, 1);
+            assert_eq!(display.active_pixel_count(), 1);
+            display.clear_all_planes();
+        }
+    }
+}
+
+#[test]
+fn last_draw() {
+    let mut display = Display::new();
+    display.draw(vec![vec![1, 1, 1], vec![1]], 70, 3);
+    assert_eq!((display.last_draw_x, display.last_draw_y), (6, 3));
+    assert_eq!((display.last_draw_width, display.last_draw_height), (3, 2));
+    assert!(!display.last_draw_collision);
+
+    display.draw_raw_sprite(
+        6,
+        4,
+        &[0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00],
+        16,
+        DrawFlags::default(),
+    );
+    assert_eq!((display.last_draw_x, display.last_draw_y), (6, 4));
+    assert_eq!((display.last_draw_width, display.last_draw_height), (16, 3));
+    assert!(display.last_draw_collision);
+}
+
+#[test]
+fn format_display() {
+    let mut display = Display::new();
+    assert_eq!(display.to_string(), "[64x32 plane=1]");
+    display.hires(false);
+    display.draw(vec![vec![1]], 0, 0);
+    display.plane(3).unwrap();
+    assert_eq!(display.to_string(), "[128x64 dirty plane=3]");
+}
+
+#[test]
+fn get_plane() {
+    let mut display = Display::new();
+    display.plane(1).unwrap();
+    display.draw(vec![vec![1, 0, 1]], 0, 0);
+    display.plane(2).unwrap();
+    display.draw(vec![vec![0, 1, 1]], 0, 0);
+    display.draw(vec![vec![1]], 63, 31);
+
+    let plane1 = display.get_plane(1);
+    let plane2 = display.get_plane(2);
+    assert_eq!(plane1[0][..3], [true, false, true]);
+    assert_eq!(plane2[0][..3], [false, true, true]);
+    assert!(plane2[31][63]);
+    assert!(!plane2[31][64]);
+    assert_eq!(display.get_plane(3)[0][..3], [true, true, true]);
+
+    // Pixels outside the current resolution are ignored
+    display.display[64] = 3;
+    assert!(!display.get_plane(3)[0][64]);
+
+    let bytes = display.get_plane_bytes(1);
+    assert_eq!(bytes.len(), 8 * 32);
+    assert_eq!(bytes[0], 0b1010_0000);
+    let bytes = display.get_plane_bytes(2);
+    assert_eq!(bytes[0], 0b0110_0000);
+    assert_eq!(bytes[8 * 31 + 7], 0b0000_0001);
+}
+
+#[test]
+fn switch_resolution_without_clearing() {
+    let mut display = Display::new();
+    display.hires(false);
+    display.draw(vec![vec![1]], 1, 2);
+    display.draw(vec![vec![1]], 100, 50);
+
+    display.lores(false);
+    assert_eq!(display.get_pixel(1, 2), 1);
+    assert_eq!(display.active_pixel_count(), 1);
+    assert!(!display.clear);
+
+    // Wraps around to (6, 8) in low resolution
+    display.draw(vec![vec![1]], 70, 40);
+    assert_eq!(display.get_pixel(6, 8), 1);
+
+    display.hires(false);
+    assert_eq!(display.get_pixel(70, 40), 0);
+    assert_eq!(display.get_pixel(100, 50), 1);
+    assert_eq!(display.active_pixel_count(), 3);
+}
+
+#[test]
+fn get_pixel_out_of_range() {
+    let mut display = Display::new();
+    display.hires(false);
+    display.draw(vec![vec![1]], 72, 1);
+    display.draw(vec![vec![1]], 127, 63);
+
+    // Wraps around to (72, 0) instead of reading (72, 1) on the next row
+    assert_eq!(display.get_pixel(200, 0), 0);
+    assert_eq!(display.get_pixel(200, 1), 1);
+    assert_eq!(display.get_pixel(255, 63), 1);
+    assert_eq!(display.get_pixel(255, 255), 1);
+
+    display.lores(false);
+    assert_eq!(display.get_pixel(255, 255), 0);
+}
+
+#[test]
+fn rgba_buffer() {
+    let mut display = Display::new();
+    display.hires(false);
+    display.plane(2).unwrap();
+    display.draw(vec![vec![1, 1]], 1, 0);
+    display.plane(1).unwrap();
+    display.draw(vec![vec![1, 1]], 0, 0);
+    let rgba = display.to_rgba_buffer(&[0x0000_0000, 0x1111_1111, 0x2222_2222, 0x3333_3333]);
+    assert_eq!(rgba.len(), 128 * 64 * 4);
+    assert_eq!(rgba[..4], [0x11; 4]);
+    assert_eq!(rgba[4..8], [0x33; 4]);
+    assert_eq!(rgba[8..12], [0x22; 4]);
+    assert_eq!(rgba[12..16], [0x00; 4]);
+}
+
+#[test]
+fn rgba_buffer_background_color() {
+    let palette = [0x0000_0000, 0x1111_1111, 0x2222_2222, 0x3333_3333];
+    let mut display = Display::new();
+    display.plane(1).unwrap();
+    display.draw(vec![vec![1, 0, 1]], 0, 0);
+    display.plane(2).unwrap();
+    display.draw(vec![vec![0, 1, 1]], 0, 0);
+    for background_color in 0..4 {
+        display.background_color = background_color;
+        let rgba = display.to_rgba_buffer(&palette);
+        let colors: Vec<u8> = rgba.chunks(4).take(4).map(|pixel| pixel[0]).collect();
+        assert_eq!(colors, [0x11, 0x22, 0x33, 0x11 * background_color]);
+    }
+}
+
+#[test]
+fn resolution_mode_changed() {
+    let mut display = Display::new();
+    assert!(!display.lores(false));
+    assert!(display.hires(false));
+    assert!(!display.hires(true));
+    assert!(display.lores(true));
+    assert!(!display.lores(false));
+}
+
+#[test]
+fn draw_counters() {
+    let mut display = Display::new();
+    for _ in 0..5 {
+        display.draw(vec![vec![1, 1]], 0, 0);
+    }
+    display.clear_active_plane();
+    display.draw(vec![vec![1]], 0, 0);
+    assert_eq!(display.total_draw_calls, 6);
+    assert_eq!(display.total_collision_count, 2);
+    assert_eq!(display.total_clear_count, 1);
+
+    display.clear_all_planes();
+    assert_eq!(display.total_draw_calls, 6);
+    assert_eq!(display.total_clear_count, 2);
+
+    display.reset_counters();
+    assert_eq!(
+        (
+            display.total_draw_calls,
+            display.total_collision_count,
+            display.total_clear_count
+        ),
+        (0, 0, 0)
+    );
+}
+
+#[test]
+fn can_scroll() {
+    let mut display = Display::new();
+    assert!(!display.can_scroll_vertical());
+    assert!(!display.can_scroll_horizontal());
+    display.draw(vec![vec![1]], 0, 0);
+    assert!(display.can_scroll_vertical());
+    assert!(display.can_scroll_horizontal());
+    display.scroll_right(1);
+    assert_eq!(display.get_pixel(1, 0), 1);
+    display.clear_all_planes();
+    assert!(!display.can_scroll_horizontal());
+}
+
+#[test]
+fn scroll_display_that_is_not_clear() {
+    let mut display = Display::new();
+    display.draw(vec![vec![1]], 0, 0);
+    display.draw(vec![vec![1]], 10, 10);
+    assert!(!display.clear);
+    display.scroll_down(2);
+    display.scroll_right(4);
+    assert_eq!(display.get_pixel(4, 2), 1);
+    assert_eq!(display.get_pixel(14, 12), 1);
+    assert_eq!(display.active_pixel_count(), 2);
+
+    // Erasing the last lit pixels with a sprite leaves the display marked as not clear
+    display.draw(vec![vec![1]], 4, 2);
+    display.draw(vec![vec![1]], 14, 12);
+    assert_eq!(display.active_pixel_count(), 0);
+    assert!(display.can_scroll_vertical());
+    display.scroll_down(2);
+    assert_eq!(display.active_pixel_count(), 0);
+}
+
+#[test]
+fn clear_one_plane_then_scroll_another() {
+    let mut display = Display::new();
+    display.plane(2).unwrap();
+    display.draw(vec![vec![1]], 0, 0);
+    display.plane(1).unwrap();
+    display.draw(vec![vec![1]], 5, 5);
+    display.clear_active_plane();
+    assert!(!display.clear);
+
+    display.plane(2).unwrap();
+    display.scroll_down(1);
+    assert_eq!(display.get_pixel(0, 0), 0);
+    assert_eq!(display.get_pixel(0, 1), 2);
+
+    display.clear_active_plane();
+    assert!(display.clear);
+}
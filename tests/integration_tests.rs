@@ -9,7 +9,7 @@ fn bon_test() {
     while chip8.pc != 0x30E {
         chip8.run(1).unwrap();
     }
-    if chip8.display.display
+    if chip8.display.buffer_rows()
         != [
             [
                 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -485,7 +485,7 @@ fn corax89_test() {
         chip8.run(1).unwrap();
     }
     println!("{:?}", chip8.display.display);
-    if chip8.display.display
+    if chip8.display.buffer_rows()
         != [
             [
                 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -949,3 +949,54 @@ fn corax89_test() {
         assert!(registers[n] == chip8.v[n]);
     }
 }
+
+// Runs the BC_test for 10 seconds, after which it has finished and is looping at the end.
+#[test]
+fn run_rom() {
+    let chip8 = deca::Chip8::run_rom(
+        include_bytes!("test_roms/BC_test.ch8"),
+        octopt::Options::new(octopt::Platform::Schip),
+        20,
+        600,
+    )
+    .unwrap();
+    assert_eq!(chip8.pc, 0x30E);
+    assert_eq!(chip8.v, [62, 24, 0, 8, 7, 1, 15, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(chip8.i, 0x3D0);
+}
+
+// Runs the BC_test with coverage tracking, which covers more of the ROM as the test progresses.
+#[test]
+fn coverage() {
+    let mut chip8 = deca::Chip8::new(octopt::Options::new(octopt::Platform::Schip));
+    chip8.read_rom(include_bytes!("test_roms/BC_test.ch8"));
+    assert_eq!(chip8.coverage_percentage(), None);
+    chip8.enable_coverage();
+    assert_eq!(chip8.coverage_percentage(), Some(0.0));
+
+    let mut previous = 0.0;
+    for _ in 0..60 {
+        chip8.run(20).unwrap();
+        let percentage = chip8.coverage_percentage().unwrap();
+        assert!(percentage >= previous);
+        previous = percentage;
+    }
+    assert!(previous > 0.5 && previous < 1.0);
+    let coverage = chip8.coverage_map().unwrap();
+    assert!(coverage[0x200] && coverage[0x201]);
+    assert!(coverage[0x30E]);
+    assert!(!coverage[0x1FF]);
+}
+
+// Runs the BC_test until it has finished, and checks that its results screen is drawn.
+#[test]
+fn screen_checksum() {
+    let chip8 = deca::Chip8::run_rom(
+        include_bytes!("test_roms/BC_test.ch8"),
+        octopt::Options::new(octopt::Platform::Schip),
+        20,
+        600,
+    )
+    .unwrap();
+    assert_eq!(chip8.screen_checksum(), 0x9591_E49F);
+}